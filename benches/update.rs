@@ -0,0 +1,48 @@
+//! update.rs - Cost of `ParticlesSystem::update` per pool size
+//! Run with `cargo bench --no-default-features --bench update` and again with `--features spatial-hash` to compare broadphases
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use particles_rust::{ParticlesSystem, Settings};
+
+const DT: f32 = 1.0 / 60.0;
+const WARMUP_STEPS: u32 = 2_000;
+const MEASURED_STEPS: u32 = 5_000;
+
+fn bench<const MAX_PARTICLES: usize>() {
+    // Spawn every update and fall fast enough to keep the pool full and colliding
+    let settings = Settings {
+        max_particles: MAX_PARTICLES,
+        max_particles_array: MAX_PARTICLES,
+        particle_spawn_rate: 600.0,
+        ..Settings::default()
+    };
+    let mut system: Box<ParticlesSystem<MAX_PARTICLES, 50>> = Box::new(ParticlesSystem::new(settings));
+    for _ in 0..WARMUP_STEPS {
+        system.update(DT);
+        system.drain_events().for_each(drop);
+    }
+
+    let start = Instant::now();
+    let mut events = 0;
+    for _ in 0..MEASURED_STEPS {
+        system.update(black_box(DT));
+        events += system.drain_events().count();
+    }
+    let per_update = start.elapsed() / MEASURED_STEPS;
+
+    println!(
+        "{:>5} particles: {:>10.2?} per update ({} active, {} events)",
+        MAX_PARTICLES, per_update, system.active_particles, events
+    );
+}
+
+fn main() {
+    let broadphase = if cfg!(feature = "spatial-hash") { "spatial hash" } else { "all pairs" };
+    println!("Broadphase: {}", broadphase);
+    bench::<12>();
+    bench::<64>();
+    bench::<256>();
+    bench::<512>();
+}
@@ -0,0 +1,172 @@
+//! broadphase - Uniform-grid broadphase for collision checks
+//! Fixed capacity, rebuilt every update and after every pair that moves particles, no_std compatible
+
+use heapless::Vec;
+
+use crate::Particle;
+
+// Upper bound on grid cells, the cell size grows to stay within it
+pub const GRID_CELLS: usize = 256;
+
+const NO_CELL: u16 = u16::MAX;
+
+// CHANGE: Counting-sort grid over particle positions
+// REASON: No heap allocation, one pass to bucket and one to fill
+pub struct Grid<const N: usize> {
+    cols: usize,
+    rows: usize,
+    cell_size: f32,
+    cell_of: [u16; N],
+    cell_start: [u16; GRID_CELLS + 1],
+    entries: [u16; N],
+}
+
+impl<const N: usize> Grid<N> {
+    // Cells are twice the largest active radius, so any overlapping pair
+    // (box or circle) sits in the same or an adjacent cell
+    pub fn build(particles: &[Particle; N], width: f32, height: f32) -> Self {
+        debug_assert!(N < NO_CELL as usize);
+        let max_radius = particles
+            .iter()
+            .filter(|p| p.active)
+            .fold(0.0f32, |m, p| m.max(p.radius));
+        let mut cell_size = (2.0 * max_radius).max(1.0);
+        let (mut cols, mut rows) = Self::dims(cell_size, width, height);
+        while cols * rows > GRID_CELLS {
+            cell_size *= 2.0;
+            (cols, rows) = Self::dims(cell_size, width, height);
+        }
+
+        let mut grid = Self {
+            cols,
+            rows,
+            cell_size,
+            cell_of: [NO_CELL; N],
+            cell_start: [0; GRID_CELLS + 1],
+            entries: [0; N],
+        };
+
+        // Count particles per cell, shifted by one for the prefix sum
+        for (i, p) in particles.iter().enumerate() {
+            if p.active {
+                let (cx, cy) = grid.cell_coords(p.x, p.y);
+                let cell = cy * cols + cx;
+                grid.cell_of[i] = cell as u16;
+                grid.cell_start[cell + 1] += 1;
+            }
+        }
+        for cell in 0..GRID_CELLS {
+            grid.cell_start[cell + 1] += grid.cell_start[cell];
+        }
+
+        // Fill in index order, so every cell lists its particles ascending
+        let mut fill = grid.cell_start;
+        for i in 0..N {
+            let cell = grid.cell_of[i];
+            if cell != NO_CELL {
+                grid.entries[fill[cell as usize] as usize] = i as u16;
+                fill[cell as usize] += 1;
+            }
+        }
+        grid
+    }
+
+    fn dims(cell_size: f32, width: f32, height: f32) -> (usize, usize) {
+        let cols = libm::ceilf(width.max(1.0) / cell_size) as usize;
+        let rows = libm::ceilf(height.max(1.0) / cell_size) as usize;
+        (cols.max(1), rows.max(1))
+    }
+
+    // COMPAT: Clamping keeps off-screen particles next to their neighbors
+    // PERF: Saturating float casts stand in for floor + clamp at zero
+    fn cell_coords(&self, x: f32, y: f32) -> (usize, usize) {
+        let cx = ((x / self.cell_size) as usize).min(self.cols - 1);
+        let cy = ((y / self.cell_size) as usize).min(self.rows - 1);
+        (cx, cy)
+    }
+
+    // Visit active particles j > i in the 3x3 cells around i, in no particular order
+    pub fn for_each_candidate(&self, i: usize, mut f: impl FnMut(usize)) {
+        let cell = self.cell_of[i];
+        if cell == NO_CELL {
+            return;
+        }
+        let cx = cell as usize % self.cols;
+        let cy = cell as usize / self.cols;
+        for ny in cy.saturating_sub(1)..=(cy + 1).min(self.rows - 1) {
+            for nx in cx.saturating_sub(1)..=(cx + 1).min(self.cols - 1) {
+                let n = ny * self.cols + nx;
+                let cell_entries = &self.entries[self.cell_start[n] as usize..self.cell_start[n + 1] as usize];
+                // Cells list their particles ascending, skip everything up to i
+                let skip = cell_entries.partition_point(|&j| j as usize <= i);
+                for &j in &cell_entries[skip..] {
+                    f(j as usize);
+                }
+            }
+        }
+    }
+}
+
+// PERF: Sorted insert for the few overlapping pairs, a general sort
+// costs far more at opt-level "z"
+pub fn insert_sorted<const N: usize>(out: &mut Vec<u16, N>, j: u16) {
+    let mut k = out.len();
+    if out.push(j).is_err() {
+        return;
+    }
+    while k > 0 && out[k - 1] > j {
+        out[k] = out[k - 1];
+        k -= 1;
+    }
+    out[k] = j;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Same tests as `ParticlesSystem::overlaps`
+    fn box_overlap(p1: &Particle, p2: &Particle) -> bool {
+        p1.x < p2.x + p2.radius && p1.x + p1.radius > p2.x &&
+        p1.y < p2.y + p2.radius && p1.y + p1.radius > p2.y
+    }
+
+    fn circle_overlap(p1: &Particle, p2: &Particle) -> bool {
+        let (dx, dy, r) = (p2.x - p1.x, p2.y - p1.y, p1.radius + p2.radius);
+        dx * dx + dy * dy < r * r
+    }
+
+    #[test]
+    fn test_grid_finds_every_overlapping_pair() {
+        let mut rng: u32 = 0x2468ACE1;
+        let mut next = move || {
+            rng ^= rng << 13;
+            rng ^= rng >> 17;
+            rng ^= rng << 5;
+            rng as f32 / u32::MAX as f32
+        };
+        let mut pool = [Particle::default(); 256];
+        for p in pool.iter_mut() {
+            p.x = next() * 360.0 - 20.0;
+            p.y = next() * 200.0 - 20.0;
+            p.radius = 3.0 + next() * 7.0;
+            p.active = next() > 0.1;
+        }
+
+        let grid = Grid::build(&pool, 320.0, 170.0);
+        let mut candidates: Vec<u16, 256> = Vec::new();
+        for overlap in [box_overlap, circle_overlap] {
+            for i in 0..pool.len() {
+                if !pool[i].active { continue; }
+                candidates.clear();
+                grid.for_each_candidate(i, |j| insert_sorted(&mut candidates, j as u16));
+                assert!(candidates.windows(2).all(|w| w[0] < w[1]));
+                for j in (i + 1)..pool.len() {
+                    if pool[j].active && overlap(&pool[i], &pool[j]) {
+                        assert!(candidates.contains(&(j as u16)), "missed pair {} {}", i, j);
+                    }
+                }
+            }
+        }
+    }
+}
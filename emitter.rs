@@ -0,0 +1,88 @@
+//! emitter - Spawn regions with their own rate, velocity and species
+//! Points, segments and boxes to spawn from, with a launch velocity and spread
+
+use crate::list::FixedList;
+
+// Upper bound on emitters, on top of the spawning across the screen
+pub const MAX_EMITTERS: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Region {
+    // Every particle starts at (x, y)
+    Point { x: f32, y: f32 },
+    // Anywhere on the line from (x0, y0) to (x1, y1)
+    Segment { x0: f32, y0: f32, x1: f32, y1: f32 },
+    // Anywhere in the axis-aligned box from its top-left corner
+    Box { x: f32, y: f32, width: f32, height: f32 },
+}
+
+impl Region {
+    // Point of the region for `u` and `v` in 0..1, `v` only matters for boxes
+    pub fn sample(&self, u: f32, v: f32) -> (f32, f32) {
+        match *self {
+            Region::Point { x, y } => (x, y),
+            Region::Segment { x0, y0, x1, y1 } => (x0 + (x1 - x0) * u, y0 + (y1 - y0) * u),
+            Region::Box { x, y, width, height } => (x + width * u, y + height * v),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Emitter {
+    pub region: Region,
+    // Average spawns per second
+    pub rate: f32,
+    // Initial velocity in px/s, decays with `impulse_damping` under constant motion
+    pub vx: f32,
+    pub vy: f32,
+    // Random turn of the initial velocity, radians from one side to the other
+    pub spread: f32,
+    // Species of every spawn, `None` picks one by weight like the global spawner
+    pub species: Option<u8>,
+    // Toggle with `update_settings` to open and close the emitter
+    pub enabled: bool,
+}
+
+// Unused slots of the list
+impl Default for Emitter {
+    fn default() -> Self {
+        Self {
+            region: Region::Point { x: 0.0, y: 0.0 },
+            rate: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            spread: 0.0,
+            species: None,
+            enabled: true,
+        }
+    }
+}
+
+pub type Emitters = FixedList<Emitter, MAX_EMITTERS>;
+
+// Initial velocity of `emitter` turned by `r` in 0..1 across its spread
+pub fn launch(emitter: &Emitter, r: f32) -> (f32, f32) {
+    let turn = (r - 0.5) * emitter.spread;
+    let (sin, cos) = (libm::sinf(turn), libm::cosf(turn));
+    (emitter.vx * cos - emitter.vy * sin, emitter.vx * sin + emitter.vy * cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_and_launch() {
+        let line = Region::Segment { x0: 10.0, y0: 0.0, x1: 30.0, y1: 20.0 };
+        assert_eq!(line.sample(0.5, 0.9), (20.0, 10.0));
+        let area = Region::Box { x: 100.0, y: 10.0, width: 40.0, height: 8.0 };
+        assert_eq!(area.sample(0.25, 0.5), (110.0, 14.0));
+        assert_eq!(Region::Point { x: 5.0, y: 6.0 }.sample(0.3, 0.7), (5.0, 6.0));
+
+        let faucet = Emitter { vx: 0.0, vy: 20.0, spread: core::f32::consts::PI, ..Emitter::default() };
+        assert_eq!(launch(&faucet, 0.5), (0.0, 20.0));
+        // A quarter turn at either end of the spread
+        let (vx, vy) = launch(&faucet, 0.0);
+        assert!((vx - 20.0).abs() < 1.0e-4 && vy.abs() < 1.0e-4);
+    }
+}
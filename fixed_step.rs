@@ -0,0 +1,105 @@
+//! fixed_step - Fixed timestep driver for the particle system
+//! Accumulates host time and runs whole physics steps, no_std compatible
+
+use crate::ParticlesSystem;
+
+// COMPAT: Same step as the 60 FPS simulator loop
+pub const DEFAULT_FIXED_STEP: f32 = 1.0 / 60.0;
+
+// CHANGE: Physics always advances by `step`, whatever the host rate
+// REASON: The same seed gives the same events at 60 Hz or on a 1 kHz timer
+#[derive(Copy, Clone, Debug)]
+pub struct FixedStep {
+    pub step: f32,
+    // Upper bound on steps per call, excess time is dropped after a stall
+    pub max_steps: u32,
+    accumulator: f32,
+}
+
+impl Default for FixedStep {
+    fn default() -> Self {
+        Self::new(DEFAULT_FIXED_STEP)
+    }
+}
+
+impl FixedStep {
+    // A zero or negative step is raised to the smallest positive one
+    pub fn new(step: f32) -> Self {
+        debug_assert!(step > 0.0, "fixed step must be positive");
+        Self { step: step.max(f32::EPSILON), max_steps: 8, accumulator: 0.0 }
+    }
+
+    // Add `elapsed` host seconds and run every whole step it covers
+    pub fn advance<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
+        &mut self,
+        system: &mut ParticlesSystem<MAX_PARTICLES, MAX_DUST>,
+        elapsed: f32,
+    ) -> u32 {
+        self.advance_with(elapsed, |dt| system.update(dt))
+    }
+
+    // Same as `advance` with a custom step, e.g. `session::Recorder::update`
+    pub fn advance_with(&mut self, elapsed: f32, mut step: impl FnMut(f32)) -> u32 {
+        self.accumulator += elapsed.max(0.0);
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                // PERF: Avoid the spiral of death, keep only the fractional part
+                self.accumulator %= self.step;
+                break;
+            }
+            step(self.step);
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    // Fraction of a step left in the accumulator, for `Particle::interpolated`
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParticleEvent, Settings};
+    use heapless::Vec;
+
+    fn run(host_dt: f32, steps: u32) -> Vec<ParticleEvent, 256> {
+        let mut system: ParticlesSystem<12, 50> = ParticlesSystem::new(Settings::default());
+        let mut driver = FixedStep::default();
+        let mut events = Vec::new();
+        let mut done = 0;
+        while done < steps {
+            done += driver.advance(&mut system, host_dt);
+            for event in system.drain_events() {
+                let _ = events.push(event);
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn test_host_rate_does_not_change_events() {
+        let desktop = run(DEFAULT_FIXED_STEP, 3600);
+        let firmware = run(0.001, 3600);
+        assert!(!desktop.is_empty());
+        assert_eq!(desktop, firmware);
+    }
+
+    #[test]
+    fn test_alpha_and_step_limit() {
+        let mut system: ParticlesSystem<12, 50> = ParticlesSystem::new(Settings::default());
+        let mut driver = FixedStep::new(0.01);
+        assert_eq!(driver.advance(&mut system, 0.025), 2);
+        assert!((driver.alpha() - 0.5).abs() < 1.0e-3);
+        assert_eq!(driver.advance(&mut system, 1.0), driver.max_steps);
+        assert!(driver.alpha() < 1.0);
+    }
+}
@@ -0,0 +1,125 @@
+//! gravity - Gravity direction and the screen side it pulls towards
+//! The side the gravity points at acts as the ground, no_std compatible
+
+use crate::{Particle, Settings, Side};
+
+// Unit gravity direction, straight down when `gravity_x` and `gravity_y` are both zero
+pub fn direction(settings: &Settings) -> (f32, f32) {
+    let (x, y) = (settings.gravity_x, settings.gravity_y);
+    let len = libm::sqrtf(x * x + y * y);
+    if len > 0.0 { (x / len, y / len) } else { (0.0, 1.0) }
+}
+
+// Angle of the gravity from straight down, positive towards +x
+pub fn angle(settings: &Settings) -> f32 {
+    let (x, y) = direction(settings);
+    libm::atan2f(x, y)
+}
+
+// CHANGE: Rotate the gravity, its strength stays in `gravity`
+// REASON: Tilt the world from a CV or an accelerometer
+pub fn set_angle(settings: &mut Settings, angle: f32) {
+    settings.gravity_x = libm::sinf(angle);
+    settings.gravity_y = libm::cosf(angle);
+}
+
+// Side acting as the ground, the one the gravity points at most
+// COMPAT: Ties go to the bottom or top
+pub fn floor((gx, gy): (f32, f32)) -> Side {
+    if libm::fabsf(gy) >= libm::fabsf(gx) {
+        if gy >= 0.0 { Side::Bottom } else { Side::Top }
+    } else if gx > 0.0 {
+        Side::Right
+    } else {
+        Side::Left
+    }
+}
+
+// Unit vector pointing out of the screen through `side`
+pub fn inward(side: Side) -> (f32, f32) {
+    match side {
+        Side::Left => (-1.0, 0.0),
+        Side::Right => (1.0, 0.0),
+        Side::Top => (0.0, -1.0),
+        Side::Bottom => (0.0, 1.0),
+    }
+}
+
+// Whether the sides next to `side` are the left and right ones
+pub fn is_horizontal(side: Side) -> bool {
+    matches!(side, Side::Top | Side::Bottom)
+}
+
+// Position of (x, y) along `side` and the length of `side`, zones and spawns run along it
+pub fn along(side: Side, settings: &Settings, x: f32, y: f32) -> (f32, f32) {
+    if is_horizontal(side) {
+        (x, settings.screen_width as f32)
+    } else {
+        (y, settings.ground_level as f32)
+    }
+}
+
+// Position of `side` along `inward(side)`, the ground line for the bottom
+pub fn level(side: Side, settings: &Settings) -> f32 {
+    match side {
+        Side::Left | Side::Top => 0.0,
+        Side::Right => settings.screen_width as f32,
+        Side::Bottom => settings.ground_level as f32,
+    }
+}
+
+// Position of (x, y) along `inward(side)`, `side` is reached at `level`
+pub fn depth(side: Side, x: f32, y: f32) -> f32 {
+    let (ix, iy) = inward(side);
+    x * ix + y * iy
+}
+
+// Put `p` on `side` with `speed` towards it, the other coordinate and velocity stay
+pub fn place(side: Side, settings: &Settings, p: &mut Particle, speed: f32) {
+    match side {
+        Side::Left => (p.x, p.vx) = (0.0, -speed),
+        Side::Right => (p.x, p.vx) = (settings.screen_width as f32, speed),
+        Side::Top => (p.y, p.vy) = (0.0, -speed),
+        Side::Bottom => (p.y, p.vy) = (settings.ground_level as f32, speed),
+    }
+}
+
+// A vector as (along, across) the gravity, across is +x when falling down
+pub fn split((x, y): (f32, f32), (gx, gy): (f32, f32)) -> (f32, f32) {
+    (x * gx + y * gy, x * gy - y * gx)
+}
+
+// Inverse of `split`
+pub fn join(along: f32, across: f32, (gx, gy): (f32, f32)) -> (f32, f32) {
+    (along * gx + across * gy, along * gy - across * gx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_floor_and_split() {
+        let mut settings = Settings::default();
+        assert_eq!(direction(&settings), (0.0, 1.0));
+        assert_eq!(floor(direction(&settings)), Side::Bottom);
+
+        // Straight down keeps every component bit for bit
+        let v = (3.25, -7.5);
+        assert_eq!(split(v, (0.0, 1.0)), (-7.5, 3.25));
+        assert_eq!(join(-7.5, 3.25, (0.0, 1.0)), v);
+
+        set_angle(&mut settings, core::f32::consts::FRAC_PI_3);
+        let g = direction(&settings);
+        assert_eq!(floor(g), Side::Right);
+        assert!((angle(&settings) - core::f32::consts::FRAC_PI_3).abs() < 1.0e-6);
+        let (along, across) = split(v, g);
+        let (x, y) = join(along, across, g);
+        assert!((x - v.0).abs() < 1.0e-5 && (y - v.1).abs() < 1.0e-5);
+
+        assert_eq!(floor((-0.6, -0.8)), Side::Top);
+        assert_eq!(floor((-0.8, 0.6)), Side::Left);
+        assert_eq!(depth(Side::Top, 5.0, 2.0), -2.0);
+        assert_eq!(level(Side::Right, &settings), 320.0);
+    }
+}
@@ -0,0 +1,415 @@
+//! headless.rs - Desktop runner for particles algorithm without a window
+//! Runs the system at a fixed dt and exports what it produced
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use particles_rust::midi::{write_smf, MidiConfig, MidiEncoder, MidiMessage};
+use particles_rust::preset::parse_preset;
+use particles_rust::session::Replay;
+use particles_rust::synth::{Synth, VoiceConfig};
+use particles_rust::{ParticleEvent, ParticlesSystem, Settings, Side};
+
+// CHANGE: Options parsed by hand
+// REASON: Keep the desktop tools free of extra dependencies
+struct Options {
+    seconds: f32,
+    dt: f32,
+    seed: Option<u32>,
+    preset_path: Option<String>,
+    midi_path: Option<String>,
+    wav_path: Option<String>,
+    sample_rate: u32,
+    log_path: Option<String>,
+    log_format: LogFormat,
+    replay_path: Option<String>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum LogFormat {
+    Csv,
+    Json,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            seconds: 60.0,
+            dt: 1.0 / 60.0,
+            seed: None,
+            preset_path: None,
+            midi_path: None,
+            wav_path: None,
+            sample_rate: 48_000,
+            log_path: None,
+            log_format: LogFormat::Csv,
+            replay_path: None,
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: particles-headless [--seconds N] [--dt SECONDS] [--seed N] [--preset FILE] [--midi FILE] [--wav FILE] [--sample-rate HZ] [--log FILE|-] [--format csv|json] [--replay FILE]");
+    process::exit(2);
+}
+
+fn parse_seed(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--seconds" => options.seconds = value().parse().unwrap_or_else(|_| usage()),
+            "--dt" => options.dt = value().parse().unwrap_or_else(|_| usage()),
+            "--seed" => options.seed = Some(parse_seed(&value()).unwrap_or_else(|| usage())),
+            "--preset" => options.preset_path = Some(value()),
+            "--midi" => options.midi_path = Some(value()),
+            "--wav" => options.wav_path = Some(value()),
+            "--sample-rate" => options.sample_rate = value().parse().unwrap_or_else(|_| usage()),
+            "--log" => options.log_path = Some(value()),
+            "--replay" => options.replay_path = Some(value()),
+            "--format" => {
+                options.log_format = match value().as_str() {
+                    "csv" => LogFormat::Csv,
+                    "json" => LogFormat::Json,
+                    _ => usage(),
+                }
+            }
+            _ => usage(),
+        }
+    }
+    if options.dt <= 0.0 || options.seconds < 0.0 || options.sample_rate == 0 {
+        usage();
+    }
+    options
+}
+
+// CHANGE: 16-bit mono PCM WAV writer
+// REASON: Render files on headless CI machines
+fn wav_bytes(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // Mono
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Left => "left",
+        Side::Right => "right",
+        Side::Top => "top",
+        Side::Bottom => "bottom",
+    }
+}
+
+// One logged value, written as it fits each format
+enum Value {
+    Number(String),
+    Name(&'static str),
+    Flag(bool),
+    Null,
+}
+
+impl Value {
+    fn number(v: impl std::fmt::Display) -> Self {
+        Value::Number(v.to_string())
+    }
+
+    fn optional(v: Option<impl std::fmt::Display>) -> Self {
+        v.map_or(Value::Null, Value::number)
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Value::Number(v) => v.clone(),
+            Value::Name(name) => name.to_string(),
+            Value::Flag(flag) => (*flag as u8).to_string(),
+            Value::Null => String::new(),
+        }
+    }
+
+    fn json(&self) -> String {
+        match self {
+            Value::Number(v) => v.clone(),
+            Value::Name(name) => format!(r#""{}""#, name),
+            Value::Flag(flag) => flag.to_string(),
+            Value::Null => "null".into(),
+        }
+    }
+}
+
+// CSV columns after tick, time and kind, the header lists them in this order
+const COLUMNS: [&str; 14] = [
+    "a", "b", "output", "radius", "bounce", "level", "speed", "zone", "floor", "species",
+    "ground_output", "collision_output", "trigger", "collision_trigger",
+];
+
+// CSV column, JSON key and value of one logged field
+type Field = (&'static str, &'static str, Value);
+
+// Kind and fields of an event, in JSON key order
+fn event_fields(event: &ParticleEvent) -> (&'static str, Vec<Field>) {
+    match *event {
+        ParticleEvent::GroundHit { index, output, radius, bounce, level, speed, zone, floor, species, .. } => (
+            "ground",
+            vec![
+                ("a", "index", Value::number(index)),
+                ("output", "output", Value::number(output)),
+                ("radius", "radius", Value::number(radius)),
+                ("bounce", "bounce", Value::number(bounce)),
+                ("level", "level", Value::number(level)),
+                ("speed", "speed", Value::number(speed)),
+                ("zone", "zone", Value::optional(zone)),
+                ("floor", "floor", Value::Name(side_name(floor))),
+                ("species", "species", Value::optional(species)),
+            ],
+        ),
+        ParticleEvent::ObstacleHit { index, obstacle, output, radius, .. } => (
+            "obstacle",
+            vec![
+                ("a", "index", Value::number(index)),
+                ("b", "obstacle", Value::number(obstacle)),
+                ("output", "output", Value::number(output)),
+                ("radius", "radius", Value::number(radius)),
+            ],
+        ),
+        ParticleEvent::EdgeExit { index, side, output, radius, .. } => (
+            "edge",
+            vec![
+                ("a", "index", Value::number(index)),
+                ("b", "side", Value::Name(side_name(side))),
+                ("output", "output", Value::number(output)),
+                ("radius", "radius", Value::number(radius)),
+            ],
+        ),
+        ParticleEvent::Collision { a, b, output, radius, .. } => (
+            "collision",
+            vec![
+                ("a", "a", Value::number(a)),
+                ("b", "b", Value::number(b)),
+                ("output", "output", Value::number(output)),
+                ("radius", "radius", Value::number(radius)),
+            ],
+        ),
+        ParticleEvent::Merge { a, b, output, radius, .. } => (
+            "merge",
+            vec![
+                ("a", "a", Value::number(a)),
+                ("b", "b", Value::number(b)),
+                ("output", "output", Value::number(output)),
+                ("radius", "radius", Value::number(radius)),
+            ],
+        ),
+    }
+}
+
+fn event_time(event: &ParticleEvent) -> f32 {
+    match *event {
+        ParticleEvent::GroundHit { time, .. }
+        | ParticleEvent::ObstacleHit { time, .. }
+        | ParticleEvent::EdgeExit { time, .. }
+        | ParticleEvent::Collision { time, .. }
+        | ParticleEvent::Merge { time, .. } => time,
+    }
+}
+
+// CHANGE: Per-tick event and output log
+// REASON: Regression scripts diff runs line by line
+struct EventLog {
+    out: BufWriter<Box<dyn Write>>,
+    format: LogFormat,
+}
+
+impl EventLog {
+    // `-` logs to stdout
+    fn open(path: &str, format: LogFormat) -> io::Result<Self> {
+        let out: Box<dyn Write> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(File::create(path)?)
+        };
+        let mut log = Self { out: BufWriter::new(out), format };
+        if format == LogFormat::Csv {
+            writeln!(log.out, "tick,time,kind,{}", COLUMNS.join(","))?;
+        }
+        Ok(log)
+    }
+
+    // CHANGE: Both formats rendered from one field list
+    // REASON: A new field is one entry, CSV columns cannot drift apart
+    fn row(&mut self, tick: u64, time: f32, kind: &str, fields: &[Field]) -> io::Result<()> {
+        match self.format {
+            LogFormat::Csv => {
+                let cells: Vec<String> = COLUMNS
+                    .iter()
+                    .map(|&column| {
+                        let field = fields.iter().find(|(c, _, _)| *c == column);
+                        field.map(|(_, _, value)| value.csv()).unwrap_or_default()
+                    })
+                    .collect();
+                writeln!(self.out, "{},{},{},{}", tick, time, kind, cells.join(","))
+            }
+            LogFormat::Json => {
+                let mut line = format!(r#"{{"tick":{},"time":{},"kind":"{}""#, tick, time, kind);
+                for (_, key, value) in fields {
+                    line.push_str(&format!(r#","{}":{}"#, key, value.json()));
+                }
+                writeln!(self.out, "{}}}", line)
+            }
+        }
+    }
+
+    fn event(&mut self, tick: u64, event: &ParticleEvent) -> io::Result<()> {
+        let (kind, fields) = event_fields(event);
+        self.row(tick, event_time(event), kind, &fields)
+    }
+
+    fn state(&mut self, tick: u64, time: f32, outputs: (u16, u16, bool, bool)) -> io::Result<()> {
+        let (ground, collision, trigger, collision_trigger) = outputs;
+        self.row(tick, time, "state", &[
+            ("ground_output", "ground_output", Value::number(ground)),
+            ("collision_output", "collision_output", Value::number(collision)),
+            ("trigger", "trigger", Value::Flag(trigger)),
+            ("collision_trigger", "collision_trigger", Value::Flag(collision_trigger)),
+        ])
+    }
+}
+
+fn load_preset(path: &str) -> Settings {
+    let text = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Cannot read {}: {}", path, err);
+        process::exit(1);
+    });
+    parse_preset(&text, Settings::default()).unwrap_or_else(|err| {
+        eprintln!("{}:{}: {:?}", path, err.line, err.kind);
+        process::exit(1);
+    })
+}
+
+fn fail(what: &str, err: io::Error) -> ! {
+    eprintln!("Cannot write {}: {}", what, err);
+    process::exit(1);
+}
+
+fn write_file(path: &str, bytes: &[u8]) {
+    if let Err(err) = fs::write(path, bytes) {
+        fail(path, err);
+    }
+}
+
+fn main() {
+    let options = parse_args();
+
+    // CHANGE: A recorded session replaces seed, dt and duration
+    // REASON: Reproduce a simulator session bit for bit
+    let replay_data = options.replay_path.as_deref().map(|path| {
+        fs::read(path).unwrap_or_else(|err| {
+            eprintln!("Cannot read {}: {}", path, err);
+            process::exit(1);
+        })
+    });
+    let mut replay = replay_data.as_deref().map(|data| {
+        Replay::new(data).unwrap_or_else(|err| {
+            eprintln!("Invalid session log: {:?}", err);
+            process::exit(1);
+        })
+    });
+    let settings = match &replay {
+        Some(replay) => replay.settings(),
+        None => {
+            let mut settings = match &options.preset_path {
+                Some(path) => load_preset(path),
+                None => Settings::default(),
+            };
+            if let Some(seed) = options.seed {
+                settings.rng_seed = seed;
+            }
+            settings
+        }
+    };
+    let mut system: ParticlesSystem<12, 50> = ParticlesSystem::new(settings);
+    let mut encoder = MidiEncoder::new(MidiConfig::from_settings(&settings));
+    let mut messages: Vec<MidiMessage> = Vec::new();
+    let mut synth: Synth<8> = Synth::new(VoiceConfig::from_settings(&settings, options.sample_rate as f32));
+    let mut samples: Vec<f32> = Vec::new();
+    let mut log = options.log_path.as_deref().map(|path| {
+        EventLog::open(path, options.log_format).unwrap_or_else(|err| fail(path, err))
+    });
+
+    let steps = (options.seconds / options.dt).round() as u64;
+    let mut tick = 0;
+    loop {
+        match replay.as_mut() {
+            Some(replay) => match replay.step(&mut system) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    eprintln!("Invalid session log: {:?}", err);
+                    process::exit(1);
+                }
+            },
+            None if tick == steps => break,
+            None => system.update(options.dt),
+        }
+        encoder.advance(system.time, |m| messages.push(m));
+        for event in system.drain_events() {
+            encoder.encode(&event, |m| messages.push(m));
+            synth.trigger(&event);
+            if let Some(log) = log.as_mut() {
+                log.event(tick, &event).unwrap_or_else(|err| fail("log", err));
+            }
+        }
+        if let Some(log) = log.as_mut() {
+            log.state(tick, system.time, system.get_outputs()).unwrap_or_else(|err| fail("log", err));
+        }
+        if options.wav_path.is_some() {
+            // COMPAT: Sample count follows simulated time, not the tick count
+            let target = (system.time as f64 * options.sample_rate as f64).round() as usize;
+            let start = samples.len();
+            samples.resize(target.max(start), 0.0);
+            synth.render(&mut samples[start..]);
+        }
+        tick += 1;
+    }
+    encoder.flush(|m| messages.push(m));
+    if let Some(mut log) = log {
+        log.out.flush().unwrap_or_else(|err| fail("log", err));
+    }
+
+    if let Some(path) = &options.midi_path {
+        // COMPAT: Stable sort keeps the emission order of equal timestamps
+        messages.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let mut file = Vec::new();
+        write_smf(messages.iter().copied(), |bytes| file.extend_from_slice(bytes));
+        write_file(path, &file);
+        eprintln!("Wrote {} MIDI messages to {}", messages.len(), path);
+    }
+
+    if let Some(path) = &options.wav_path {
+        write_file(path, &wav_bytes(&samples, options.sample_rate));
+        eprintln!("Wrote {:.1} s of audio to {}", samples.len() as f32 / options.sample_rate as f32, path);
+    }
+}
@@ -4,7 +4,7 @@
 use embedded_graphics::{
     pixelcolor::Rgb565,
     prelude::*,
-    primitives::{Rectangle, PrimitiveStyle, Line},
+    primitives::{Circle, Rectangle, PrimitiveStyle, Line},
     text::{Text, Baseline},
     mono_font::{ascii::FONT_6X10, MonoTextStyle},
     Pixel,
@@ -12,12 +12,23 @@ use embedded_graphics::{
 use embedded_graphics_simulator::{
     SimulatorDisplay, Window, OutputSettingsBuilder, SimulatorEvent,
 };
-use std::time::{Duration, Instant};
+use std::fs;
+use std::net::UdpSocket;
+use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::thread;
 
 // CHANGE: Import particles module
 // REASON: Separation of concerns - main handles UI, particles handles algorithm
-use particles_rust::{ParticlesSystem, Settings};
+use particles_rust::{CollisionMode, ParticleEvent, ParticlesSystem, Settings, Side};
+use particles_rust::emitter::Region;
+use particles_rust::fixed_step::FixedStep;
+use particles_rust::force::Polarity;
+use particles_rust::gravity;
+use particles_rust::obstacle::Shape;
+use particles_rust::osc::{self, MAX_OSC_PACKET};
+use particles_rust::preset::{parse_preset, write_preset};
+use particles_rust::session::Recorder;
 
 // CHANGE: Separate UI state from particle system
 // REASON: Clean separation between rendering and algorithm
@@ -27,9 +38,6 @@ struct UiState {
     wind: f32,
     max_particles: usize,
     verbose: bool,
-    
-    // Display info
-    info_message: String,
 }
 
 impl Default for UiState {
@@ -39,11 +47,130 @@ impl Default for UiState {
             wind: 0.1,
             max_particles: 6,
             verbose: false,
-            info_message: String::new(),
         }
     }
 }
 
+// CHANGE: Command line options for event output
+// REASON: Observe events outside of the window, e.g. from SuperCollider
+struct Options {
+    osc_host: String,
+    osc_port: Option<u16>,
+    osc_prefix: String,
+    record_path: Option<String>,
+    preset_path: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            osc_host: "127.0.0.1".to_string(),
+            osc_port: None,
+            osc_prefix: "/particles".to_string(),
+            record_path: None,
+            preset_path: None,
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: particles [--osc-port PORT] [--osc-host HOST] [--osc-prefix /ADDRESS] [--record FILE] [--preset FILE]");
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--osc-host" => options.osc_host = value(),
+            "--osc-port" => options.osc_port = Some(value().parse().unwrap_or_else(|_| usage())),
+            "--osc-prefix" => options.osc_prefix = value(),
+            "--record" => options.record_path = Some(value()),
+            "--preset" => options.preset_path = Some(value()),
+            _ => usage(),
+        }
+    }
+    if !options.osc_prefix.starts_with('/') {
+        usage();
+    }
+    if !osc::prefix_fits(&options.osc_prefix) {
+        eprintln!("OSC prefix too long, messages must fit in {} bytes", MAX_OSC_PACKET);
+        usage();
+    }
+    options
+}
+
+// CHANGE: UDP sender for OSC messages
+// REASON: Encoding lives in the no_std library, only the socket is desktop specific
+struct OscOutput {
+    socket: UdpSocket,
+    prefix: String,
+    buf: [u8; MAX_OSC_PACKET],
+}
+
+impl OscOutput {
+    fn connect(host: &str, port: u16, prefix: &str) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect((host, port))?;
+        Ok(Self {
+            socket,
+            prefix: prefix.to_string(),
+            buf: [0; MAX_OSC_PACKET],
+        })
+    }
+
+    fn send_event(&mut self, event: &ParticleEvent) {
+        if let Some(len) = osc::encode_event(&self.prefix, event, &mut self.buf) {
+            // PERF: Dropped datagrams are not worth stalling the frame for
+            let _ = self.socket.send(&self.buf[..len]);
+        }
+    }
+
+    fn send_outputs(&mut self, outputs: (u16, u16, bool, bool)) {
+        if let Some(len) = osc::encode_outputs(&self.prefix, outputs, &mut self.buf) {
+            let _ = self.socket.send(&self.buf[..len]);
+        }
+    }
+}
+
+// CHANGE: Route settings changes through the recorder when recording
+// REASON: Replays must see every G/W/P change at the same step
+fn apply_settings<const MAX_PARTICLES: usize, const MAX_DUST: usize, W: FnMut(&[u8])>(
+    system: &mut ParticlesSystem<MAX_PARTICLES, MAX_DUST>,
+    recorder: &mut Option<Recorder<W>>,
+    settings: Settings,
+) {
+    match recorder {
+        Some(recorder) => recorder.update_settings(system, settings),
+        None => system.update_settings(settings),
+    }
+}
+
+// CHANGE: Preset files on disk
+// REASON: Keep preset libraries per performance
+fn load_preset(path: &str) -> Settings {
+    let text = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Cannot read {}: {}", path, err);
+        process::exit(1);
+    });
+    parse_preset(&text, Settings::default()).unwrap_or_else(|err| {
+        eprintln!("{}:{}: {:?}", path, err.line, err.kind);
+        process::exit(1);
+    })
+}
+
+fn save_preset(settings: &Settings) -> std::io::Result<String> {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let path = format!("particles-{}.preset", stamp);
+    let mut text = String::new();
+    // PERF: Writing into a String cannot fail
+    let _ = write_preset(settings, &mut text);
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
 // CHANGE: Extract rendering logic to separate function
 // REASON: Modularity and maintainability
 fn render_particles<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
@@ -51,6 +178,7 @@ fn render_particles<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
     system: &ParticlesSystem<MAX_PARTICLES, MAX_DUST>,
     ui: &UiState,
     settings: &Settings,
+    alpha: f32,
 ) {
     // Theme colors
     let bg_color = Rgb565::BLACK;
@@ -68,19 +196,119 @@ fn render_particles<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
     .into_styled(PrimitiveStyle::with_stroke(ground_color, 1))
     .draw(display).unwrap();
     
+    // Draw the wall acting as the ground when the gravity is tilted
+    let wall_x = match gravity::floor(gravity::direction(settings)) {
+        Side::Left => Some(0),
+        Side::Right => Some(settings.screen_width - 1),
+        Side::Top | Side::Bottom => None,
+    };
+    if let Some(x) = wall_x {
+        Line::new(Point::new(x, 0), Point::new(x, settings.ground_level))
+            .into_styled(PrimitiveStyle::with_stroke(ground_color, 1))
+            .draw(display).unwrap();
+    }
+    
+    // Draw ground zones as ticks at their ends, lit while their trigger is high
+    for (k, zone) in settings.ground_zones.as_slice().iter().enumerate() {
+        let color = if system.get_zone_output(k).1 { text_color } else { ground_color };
+        let style = PrimitiveStyle::with_stroke(color, 1);
+        let (x0, x1) = (zone.x0 as i32, zone.x1 as i32);
+        let y = settings.ground_level;
+        for x in [x0, x1] {
+            Line::new(Point::new(x, y - 2), Point::new(x, y + 2))
+                .into_styled(style)
+                .draw(display).unwrap();
+        }
+        if color == text_color {
+            Line::new(Point::new(x0, y), Point::new(x1, y))
+                .into_styled(style)
+                .draw(display).unwrap();
+        }
+    }
+    
+    // Draw obstacles, lit while their trigger is high
+    for (k, obstacle) in settings.obstacles.as_slice().iter().enumerate() {
+        let color = if system.get_obstacle_output(k).1 { text_color } else { ground_color };
+        let style = PrimitiveStyle::with_stroke(color, 1);
+        match obstacle.shape {
+            Shape::Segment { x0, y0, x1, y1 } => Line::new(
+                Point::new(x0 as i32, y0 as i32),
+                Point::new(x1 as i32, y1 as i32),
+            )
+            .into_styled(style)
+            .draw(display).unwrap(),
+            Shape::Box { x, y, width, height } => Rectangle::new(
+                Point::new(x as i32, y as i32),
+                Size::new(width as u32, height as u32),
+            )
+            .into_styled(style)
+            .draw(display).unwrap(),
+        }
+    }
+    
+    // Draw force points, rings for attractors and crosses for repulsors
+    for point in settings.force_points.as_slice() {
+        let center = Point::new(point.x as i32, point.y as i32);
+        let style = PrimitiveStyle::with_stroke(ground_color, 1);
+        match point.polarity {
+            Polarity::Attract => Circle::with_center(center, 7)
+                .into_styled(style)
+                .draw(display).unwrap(),
+            Polarity::Repel => {
+                Line::new(center - Point::new(3, 3), center + Point::new(3, 3))
+                    .into_styled(style)
+                    .draw(display).unwrap();
+                Line::new(center + Point::new(-3, 3), center + Point::new(3, -3))
+                    .into_styled(style)
+                    .draw(display).unwrap();
+            }
+        }
+    }
+    
+    // Draw enabled emitters, dotted so they stand apart from obstacles
+    for emitter in settings.emitters.as_slice().iter().filter(|e| e.enabled) {
+        let (x0, y0, x1, y1) = match emitter.region {
+            Region::Point { x, y } => (x, y, x, y),
+            Region::Segment { x0, y0, x1, y1 } => (x0, y0, x1, y1),
+            Region::Box { x, y, width, height } => (x, y, x + width, y + height),
+        };
+        let steps = ((x1 - x0).abs().max((y1 - y0).abs()) / 4.0) as i32;
+        for n in 0..=steps {
+            let t = if steps > 0 { n as f32 / steps as f32 } else { 0.0 };
+            let point = Point::new((x0 + (x1 - x0) * t) as i32, (y0 + (y1 - y0) * t) as i32);
+            Pixel(point, text_color).draw(display).unwrap();
+        }
+    }
+    
     // Draw particles
     for particle in &system.particle_pool {
         if particle.active {
             // COMPAT: Same brightness calculation as original
             let brightness = ((particle.radius * 1.5) as u8).min(31);
-            let color = Rgb565::new(0, brightness * 2, brightness * 3);
+            // Species are told apart by a red tint
+            let tint = particle.species.map_or(0, |k| (k + 1) * 7).min(31);
+            let color = Rgb565::new(tint, brightness * 2, brightness * 3);
             
-            Rectangle::new(
-                Point::new(particle.x as i32, particle.y as i32),
-                Size::new(particle.radius as u32, particle.radius as u32),
-            )
-            .into_styled(PrimitiveStyle::with_fill(color))
-            .draw(display).unwrap();
+            // CHANGE: Draw between the last two physics steps
+            // REASON: Smooth motion with a fixed timestep
+            let (x, y) = particle.interpolated(alpha);
+            let style = PrimitiveStyle::with_fill(color);
+            match settings.collision_mode {
+                CollisionMode::Box => Rectangle::new(
+                    Point::new(x as i32, y as i32),
+                    Size::new(particle.radius as u32, particle.radius as u32),
+                )
+                .into_styled(style)
+                .draw(display).unwrap(),
+                // CHANGE: Circles centered on (x, y) in circle mode
+                // REASON: What is drawn matches what collides
+                CollisionMode::Circle => Circle::with_center(
+                    Point::new(x as i32, y as i32),
+                    (particle.radius * 2.0) as u32,
+                )
+                .into_styled(style)
+                .draw(display).unwrap(),
+            }
         }
     }
     
@@ -143,7 +371,7 @@ fn render_particles<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
     // Instructions
     let instructions_style = MonoTextStyle::new(&FONT_6X10, Rgb565::new(0, 20, 40));
     Text::with_baseline(
-        "Space: Verbose | G: Gravity | W: Wind | P: Particles | Q: Quit",
+        "Space: Verbose | G/W/P: Adjust | S: Save | Q: Quit",
         Point::new(5, settings.screen_height - 5),
         instructions_style,
         Baseline::Top,
@@ -152,9 +380,21 @@ fn render_particles<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
 }
 
 fn main() {
-    // CHANGE: Initialize settings with defaults
+    let options = parse_args();
+    let mut osc_output = options.osc_port.map(|port| {
+        OscOutput::connect(&options.osc_host, port, &options.osc_prefix).unwrap_or_else(|err| {
+            eprintln!("Cannot open OSC output to {}:{}: {}", options.osc_host, port, err);
+            process::exit(1);
+        })
+    });
+    
+    // CHANGE: Initialize settings with defaults or a preset file
     // REASON: Configuration externalization
-    let mut settings = Settings::default();
+    // The simulator shows off the dust wake, off in the library defaults
+    let mut settings = match &options.preset_path {
+        Some(path) => load_preset(path),
+        None => Settings { dust_wake_strength: 200.0, ..Settings::default() },
+    };
     
     // Create display
     let mut display = SimulatorDisplay::new(Size::new(
@@ -167,7 +407,21 @@ fn main() {
     // CHANGE: Create system with explicit const generics
     // REASON: Compile-time array size specification
     let mut system: ParticlesSystem<12, 50> = ParticlesSystem::new(settings);
-    let mut ui = UiState::default();
+    let mut ui = UiState {
+        gravity: settings.gravity,
+        wind: settings.wind,
+        max_particles: settings.max_particles,
+        ..UiState::default()
+    };
+    let mut stepper = FixedStep::default();
+    
+    // CHANGE: Optional session recording, written on quit
+    // REASON: Replay good-sounding moments with particles-headless --replay
+    let mut session_log: Vec<u8> = Vec::new();
+    let mut recorder = options
+        .record_path
+        .as_ref()
+        .map(|_| Recorder::new(&settings, |bytes: &[u8]| session_log.extend_from_slice(bytes)));
     
     // Timing
     let mut last_update = Instant::now();
@@ -180,26 +434,84 @@ fn main() {
     println!("  G: Adjust gravity");
     println!("  W: Adjust wind");
     println!("  P: Adjust max particles");
+    println!("  S: Save preset");
     println!("  Q: Quit");
     println!("\nNOTE: This refactored version outputs normalized u16 values");
     println!("instead of pitch/scale for embedded system compatibility.");
+    if let Some(path) = &options.record_path {
+        println!("\nRecording session to {}", path);
+    }
+    if let Some(port) = options.osc_port {
+        println!("\nSending OSC to {}:{} under {}", options.osc_host, port, options.osc_prefix);
+    }
     
     'main_loop: loop {
         let now = Instant::now();
         let dt = now.duration_since(last_update).as_secs_f32();
         last_update = now;
         
-        // Update physics
-        system.update(dt);
+        // CHANGE: Update physics in fixed steps
+        // REASON: Frame jitter no longer changes the outcome
+        match recorder.as_mut() {
+            Some(recorder) => stepper.advance_with(dt, |step| recorder.update(&mut system, step)),
+            None => stepper.advance(&mut system, dt),
+        };
+        
+        // CHANGE: Drain every event produced by this update
+        // REASON: Several hits per frame are no longer collapsed into one
+        for event in system.drain_events() {
+            if let Some(out) = osc_output.as_mut() {
+                out.send_event(&event);
+            }
+            if ui.verbose {
+                match event {
+                    ParticleEvent::GroundHit { index, output, time, floor, .. } if floor != Side::Bottom => {
+                        println!("[{:8.3}] {:?} wall hit: particle {} output {}", time, floor, index, output);
+                    }
+                    ParticleEvent::GroundHit { index, output, time, bounce: 0, zone: Some(k), .. } => {
+                        println!("[{:8.3}] Ground hit in zone {}: particle {} output {}", time, k, index, output);
+                    }
+                    ParticleEvent::GroundHit { index, output, time, bounce: 0, .. } => {
+                        println!("[{:8.3}] Ground hit: particle {} output {}", time, index, output);
+                    }
+                    ParticleEvent::GroundHit { index, output, time, bounce, .. } => {
+                        println!("[{:8.3}] Bounce {}: particle {} output {}", time, bounce, index, output);
+                    }
+                    ParticleEvent::ObstacleHit { index, obstacle, output, time, .. } => {
+                        println!("[{:8.3}] Obstacle {} hit: particle {} output {}", time, obstacle, index, output);
+                    }
+                    ParticleEvent::Collision { a, b, output, time, .. } => {
+                        println!("[{:8.3}] Collision: particles {}/{} output {}", time, a, b, output);
+                    }
+                    ParticleEvent::Merge { a, b, output, time, .. } => {
+                        println!("[{:8.3}] Merge: particles {}/{} output {}", time, a, b, output);
+                    }
+                    ParticleEvent::EdgeExit { index, side, output, time, .. } => {
+                        println!("[{:8.3}] Edge exit ({:?}): particle {} output {}", time, side, index, output);
+                    }
+                }
+            }
+        }
+        if let Some(out) = osc_output.as_mut() {
+            out.send_outputs(system.get_outputs());
+        }
         
         // Render
-        render_particles(&mut display, &system, &ui, &settings);
+        render_particles(&mut display, &system, &ui, &settings, stepper.alpha());
         window.update(&display);
         
         // Handle events
         for event in window.events() {
             match event {
                 SimulatorEvent::Quit => break 'main_loop,
+                // Click - move the first force point
+                SimulatorEvent::MouseButtonDown { point, .. } => {
+                    if let Some(force) = settings.force_points.as_mut_slice().first_mut() {
+                        force.x = point.x as f32;
+                        force.y = point.y as f32;
+                        apply_settings(&mut system, &mut recorder, settings);
+                    }
+                }
                 SimulatorEvent::KeyDown { keycode, .. } => {
                     let key = format!("{:?}", keycode).to_lowercase();
                     match key.as_str() {
@@ -226,9 +538,25 @@ fn main() {
                                 _ => 0.5,
                             };
                             settings.gravity = ui.gravity;
-                            system.update_settings(settings);
+                            apply_settings(&mut system, &mut recorder, settings);
                             println!("Gravity: {:.1}", ui.gravity);
                         }
+                        // T - tilt the gravity by 15 degrees
+                        "t" => {
+                            let angle = gravity::angle(&settings) + core::f32::consts::PI / 12.0;
+                            gravity::set_angle(&mut settings, angle);
+                            apply_settings(&mut system, &mut recorder, settings);
+                            println!("Gravity angle: {:.0} degrees", gravity::angle(&settings).to_degrees());
+                        }
+                        // E - open or close every emitter
+                        "e" => {
+                            let open = !settings.emitters.as_slice().iter().any(|e| e.enabled);
+                            for emitter in settings.emitters.as_mut_slice() {
+                                emitter.enabled = open;
+                            }
+                            apply_settings(&mut system, &mut recorder, settings);
+                            println!("Emitters: {}", if open { "ON" } else { "OFF" });
+                        }
                         // W - wind
                         "w" => {
                             // PERF: Cycle through preset values
@@ -246,16 +574,21 @@ fn main() {
                                 _ => 0.1,
                             };
                             settings.wind = ui.wind;
-                            system.update_settings(settings);
+                            apply_settings(&mut system, &mut recorder, settings);
                             println!("Wind: {:.1}", ui.wind);
                         }
                         // P - max particles
                         "p" => {
                             ui.max_particles = if ui.max_particles >= 12 { 1 } else { ui.max_particles + 1 };
                             settings.max_particles = ui.max_particles;
-                            system.update_settings(settings);
+                            apply_settings(&mut system, &mut recorder, settings);
                             println!("Max particles: {}", ui.max_particles);
                         }
+                        // S - save preset
+                        "s" => match save_preset(&settings) {
+                            Ok(path) => println!("Preset saved to {}", path),
+                            Err(err) => eprintln!("Cannot save preset: {}", err),
+                        },
                         // Q - quit
                         "q" => break 'main_loop,
                         _ => {}
@@ -272,5 +605,13 @@ fn main() {
         }
     }
     
+    if let (Some(recorder), Some(path)) = (recorder, &options.record_path) {
+        let _ = recorder.finish();
+        match fs::write(path, &session_log) {
+            Ok(()) => println!("Session recorded to {} ({} bytes)", path, session_log.len()),
+            Err(err) => eprintln!("Cannot write {}: {}", path, err),
+        }
+    }
+    
     println!("Thanks for playing with particles!");
 }
\ No newline at end of file
@@ -0,0 +1,327 @@
+//! midi - MIDI encoding of particle events
+//! Note-on/note-off byte stream and Standard MIDI File writer, no_std compatible
+
+use heapless::Vec;
+
+use crate::species::MAX_SPECIES;
+use crate::zone::MAX_GROUND_ZONES;
+use crate::{ParticleEvent, Settings};
+
+// Maximum number of notes waiting for their note-off
+pub const MAX_PENDING_NOTES: usize = 32;
+
+const NOTE_ON: u8 = 0x90;
+const NOTE_OFF: u8 = 0x80;
+
+// A timestamped three-byte channel message
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MidiMessage {
+    pub time: f32,
+    pub bytes: [u8; 3],
+}
+
+impl MidiMessage {
+    pub fn note_on(time: f32, channel: u8, note: u8, velocity: u8) -> Self {
+        Self { time, bytes: [NOTE_ON | (channel & 0x0F), note & 0x7F, velocity & 0x7F] }
+    }
+
+    pub fn note_off(time: f32, channel: u8, note: u8) -> Self {
+        Self { time, bytes: [NOTE_OFF | (channel & 0x0F), note & 0x7F, 0] }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MidiConfig {
+    // Channels are zero-based (0 = MIDI channel 1)
+    pub ground_channel: u8,
+    pub collision_channel: u8,
+    // Obstacle `k` plays on `obstacle_channel + k`, up to channel 16
+    pub obstacle_channel: u8,
+    pub edge_channel: u8,
+    // Fixed notes of the ground zones, `None` follows the output
+    pub zone_notes: [Option<u8>; MAX_GROUND_ZONES],
+    // Channels of the species' ground hits, `None` plays on `ground_channel`
+    pub species_channels: [Option<u8>; MAX_SPECIES],
+    // Note range the u16 output is spread over
+    pub note_low: u8,
+    pub note_high: u8,
+    // Velocity range mapped from the particle radius
+    pub velocity_min: u8,
+    pub velocity_max: u8,
+    pub radius_min: f32,
+    pub radius_max: f32,
+    // Seconds between note-on and note-off
+    pub note_length: f32,
+}
+
+impl MidiConfig {
+    // COMPAT: Note length follows the trigger gate of the CV outputs
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            ground_channel: 0,
+            collision_channel: 1,
+            obstacle_channel: 2,
+            // Above the channels of all 8 obstacles
+            edge_channel: 10,
+            note_low: 36,
+            note_high: 84,
+            velocity_min: 40,
+            velocity_max: 127,
+            radius_min: settings.particle_min_size,
+            radius_max: settings.particle_max_size,
+            note_length: settings.trigger_duration,
+            zone_notes: {
+                let mut notes = [None; MAX_GROUND_ZONES];
+                for (note, zone) in notes.iter_mut().zip(settings.ground_zones.as_slice()) {
+                    *note = zone.note;
+                }
+                notes
+            },
+            species_channels: {
+                let mut channels = [None; MAX_SPECIES];
+                for (channel, species) in channels.iter_mut().zip(settings.species.as_slice()) {
+                    *channel = species.channel;
+                }
+                channels
+            },
+        }
+    }
+}
+
+impl Default for MidiConfig {
+    fn default() -> Self {
+        Self::from_settings(&Settings::default())
+    }
+}
+
+#[derive(Copy, Clone)]
+struct PendingNote {
+    off_time: f32,
+    channel: u8,
+    note: u8,
+}
+
+// Turns particle events into note-on messages and schedules their note-offs
+pub struct MidiEncoder {
+    pub config: MidiConfig,
+    pending: Vec<PendingNote, MAX_PENDING_NOTES>,
+}
+
+impl MidiEncoder {
+    pub fn new(config: MidiConfig) -> Self {
+        Self { config, pending: Vec::new() }
+    }
+
+    pub fn note_for(&self, output: u16) -> u8 {
+        let low = self.config.note_low.min(self.config.note_high) as u32;
+        let high = self.config.note_high.max(self.config.note_low) as u32;
+        (low + ((output as u32 * (high - low + 1)) >> 16)) as u8
+    }
+
+    pub fn velocity_for(&self, radius: f32) -> u8 {
+        let span = self.config.radius_max - self.config.radius_min;
+        let t = if span > 0.0 { ((radius - self.config.radius_min) / span).clamp(0.0, 1.0) } else { 1.0 };
+        let min = self.config.velocity_min as f32;
+        let max = self.config.velocity_max as f32;
+        ((min + t * (max - min) + 0.5) as u8).clamp(1, 127)
+    }
+
+    // Encode one event, note and velocity derived from the config
+    pub fn encode(&mut self, event: &ParticleEvent, emit: impl FnMut(MidiMessage)) {
+        let (channel, output, radius, level, time) = match *event {
+            ParticleEvent::GroundHit { output, radius, level, time, species, .. } => {
+                let channel = species.and_then(|k| self.config.species_channels.get(k).copied().flatten());
+                (channel.unwrap_or(self.config.ground_channel), output, radius, level, time)
+            }
+            ParticleEvent::ObstacleHit { obstacle, output, radius, time, .. } => {
+                let channel = (self.config.obstacle_channel as usize + obstacle).min(15) as u8;
+                (channel, output, radius, 1.0, time)
+            }
+            ParticleEvent::Collision { output, radius, time, .. } | ParticleEvent::Merge { output, radius, time, .. } => {
+                (self.config.collision_channel, output, radius, 1.0, time)
+            }
+            ParticleEvent::EdgeExit { output, radius, time, .. } => (self.config.edge_channel, output, radius, 1.0, time),
+        };
+        let note = match *event {
+            ParticleEvent::GroundHit { zone: Some(k), .. } => self.config.zone_notes.get(k).copied().flatten(),
+            _ => None,
+        }
+        .unwrap_or_else(|| self.note_for(output));
+        // Bounces get softer with the rebound speed
+        let velocity = ((self.velocity_for(radius) as f32 * level + 0.5) as u8).clamp(1, 127);
+        self.encode_note(time, channel, note, velocity, emit);
+    }
+
+    // Encode an explicit note, e.g. one picked by the quantizer
+    pub fn encode_note(&mut self, time: f32, channel: u8, note: u8, velocity: u8, mut emit: impl FnMut(MidiMessage)) {
+        // Retrigger: close a still sounding identical note first
+        if let Some(pos) = self.pending.iter().position(|n| n.channel == channel && n.note == note) {
+            self.pending.remove(pos);
+            emit(MidiMessage::note_off(time, channel, note));
+        }
+        // PERF: When the pool is full, cut the note closest to its end
+        if self.pending.is_full() {
+            let first = self.pending.remove(0);
+            emit(MidiMessage::note_off(time, first.channel, first.note));
+        }
+        emit(MidiMessage::note_on(time, channel, note, velocity));
+        let off_time = time + self.config.note_length;
+        let pos = self.pending.iter().position(|n| n.off_time > off_time).unwrap_or(self.pending.len());
+        let _ = self.pending.insert(pos, PendingNote { off_time, channel, note });
+    }
+
+    // Emit every note-off due at or before `now`, in time order
+    pub fn advance(&mut self, now: f32, mut emit: impl FnMut(MidiMessage)) {
+        while let Some(first) = self.pending.first().copied() {
+            if first.off_time > now {
+                break;
+            }
+            self.pending.remove(0);
+            emit(MidiMessage::note_off(first.off_time, first.channel, first.note));
+        }
+    }
+
+    // Emit all remaining note-offs at their scheduled time
+    pub fn flush(&mut self, emit: impl FnMut(MidiMessage)) {
+        self.advance(f32::INFINITY, emit);
+    }
+}
+
+// CHANGE: Standard MIDI File writer without buffering
+// REASON: No heap allocation, the track length is found in a first pass
+pub const SMF_TICKS_PER_QUARTER: u16 = 480;
+// 120 BPM, so one second is two quarter notes
+const SMF_TEMPO_US: u32 = 500_000;
+
+fn seconds_to_ticks(time: f32) -> u32 {
+    let ticks = time * SMF_TICKS_PER_QUARTER as f32 * 1_000_000.0 / SMF_TEMPO_US as f32;
+    (ticks.max(0.0) + 0.5) as u32
+}
+
+fn vlq(value: u32) -> ([u8; 4], usize) {
+    let mut bytes = [0u8; 4];
+    let mut len = 1;
+    let mut v = value & 0x0FFF_FFFF;
+    bytes[3] = (v & 0x7F) as u8;
+    v >>= 7;
+    while v > 0 {
+        len += 1;
+        bytes[4 - len] = (v & 0x7F) as u8 | 0x80;
+        v >>= 7;
+    }
+    (bytes, len)
+}
+
+fn write_track_body<I: Iterator<Item = MidiMessage>>(messages: I, out: &mut impl FnMut(&[u8])) {
+    // Tempo meta event
+    let tempo = SMF_TEMPO_US.to_be_bytes();
+    out(&[0x00, 0xFF, 0x51, 0x03, tempo[1], tempo[2], tempo[3]]);
+    let mut last_tick = 0;
+    for message in messages {
+        let tick = seconds_to_ticks(message.time).max(last_tick);
+        let (delta, len) = vlq(tick - last_tick);
+        out(&delta[4 - len..]);
+        out(&message.bytes);
+        last_tick = tick;
+    }
+    // End of track
+    out(&[0x00, 0xFF, 0x2F, 0x00]);
+}
+
+// Write a format 0 file; `messages` is walked twice and must be sorted by time
+pub fn write_smf<I>(messages: I, mut out: impl FnMut(&[u8]))
+where
+    I: Iterator<Item = MidiMessage> + Clone,
+{
+    let mut track_len: u32 = 0;
+    write_track_body(messages.clone(), &mut |bytes: &[u8]| track_len += bytes.len() as u32);
+
+    out(b"MThd");
+    out(&6u32.to_be_bytes());
+    out(&0u16.to_be_bytes());
+    out(&1u16.to_be_bytes());
+    out(&SMF_TICKS_PER_QUARTER.to_be_bytes());
+    out(b"MTrk");
+    out(&track_len.to_be_bytes());
+    write_track_body(messages, &mut out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Side;
+
+    #[test]
+    fn test_note_off_follows_trigger_duration() {
+        let settings = Settings::default();
+        let mut encoder = MidiEncoder::new(MidiConfig::from_settings(&settings));
+        let mut messages: Vec<MidiMessage, 8> = Vec::new();
+        let hit = ParticleEvent::GroundHit { index: 0, output: u16::MAX, radius: 10.0, time: 1.0, bounce: 0, level: 1.0, speed: 8.0, zone: None, floor: Side::Bottom, species: None };
+
+        encoder.encode(&hit, |m| { let _ = messages.push(m); });
+        encoder.advance(1.01, |m| { let _ = messages.push(m); });
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].bytes, [0x90, 84, 127]);
+
+        encoder.advance(1.0 + settings.trigger_duration, |m| { let _ = messages.push(m); });
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].bytes, [0x80, 84, 0]);
+        assert_eq!(messages[1].time, 1.0 + settings.trigger_duration);
+    }
+
+    #[test]
+    fn test_zone_note_replaces_output_note() {
+        let mut settings = Settings::default();
+        let _ = settings.ground_zones.push(crate::zone::GroundZone { x0: 0.0, x1: 100.0, note: Some(38) });
+        let mut encoder = MidiEncoder::new(MidiConfig::from_settings(&settings));
+        let mut messages: Vec<MidiMessage, 8> = Vec::new();
+        for zone in [Some(0), None] {
+            let hit = ParticleEvent::GroundHit { index: 0, output: u16::MAX, radius: 10.0, time: 1.0, bounce: 0, level: 1.0, speed: 8.0, zone, floor: Side::Bottom, species: None };
+            encoder.encode(&hit, |m| { let _ = messages.push(m); });
+        }
+        assert_eq!(messages[0].bytes, [0x90, 38, 127]);
+        assert_eq!(messages[1].bytes, [0x90, 84, 127]);
+    }
+
+    #[test]
+    fn test_species_channel_replaces_ground_channel() {
+        let mut settings = Settings::default();
+        let bass = crate::species::Species { channel: Some(5), ..Default::default() };
+        let _ = settings.species.push(bass);
+        let _ = settings.species.push(crate::species::Species::default());
+        let mut encoder = MidiEncoder::new(MidiConfig::from_settings(&settings));
+        let mut messages: Vec<MidiMessage, 8> = Vec::new();
+        for (time, species) in [(1.0, Some(0)), (2.0, Some(1)), (3.0, None)] {
+            let hit = ParticleEvent::GroundHit { index: 0, output: u16::MAX, radius: 10.0, time, bounce: 0, level: 1.0, speed: 8.0, zone: None, floor: Side::Bottom, species };
+            encoder.encode(&hit, |m| { let _ = messages.push(m); });
+        }
+        assert_eq!(messages[0].bytes[0], 0x95);
+        assert!(messages[1..].iter().all(|m| m.bytes[0] & 0x0F == 0));
+    }
+
+    #[test]
+    fn test_velocity_scales_with_radius() {
+        let encoder = MidiEncoder::new(MidiConfig::default());
+        assert_eq!(encoder.velocity_for(3.0), 40);
+        assert_eq!(encoder.velocity_for(10.0), 127);
+        assert!(encoder.velocity_for(5.0) < encoder.velocity_for(8.0));
+    }
+
+    #[test]
+    fn test_smf_layout() {
+        let messages = [
+            MidiMessage::note_on(0.0, 0, 60, 100),
+            MidiMessage::note_off(0.5, 0, 60),
+        ];
+        let mut file: Vec<u8, 64> = Vec::new();
+        write_smf(messages.iter().copied(), |bytes| { let _ = file.extend_from_slice(bytes); });
+
+        assert_eq!(&file[0..4], b"MThd");
+        assert_eq!(&file[14..18], b"MTrk");
+        let track_len = u32::from_be_bytes([file[18], file[19], file[20], file[21]]) as usize;
+        assert_eq!(file.len(), 22 + track_len);
+        // 0.5 s at 120 BPM is 480 ticks, encoded as 0x83 0x60
+        assert_eq!(&file[33..37], &[0x83, 0x60, 0x80, 60]);
+        assert_eq!(&file[file.len() - 3..], &[0xFF, 0x2F, 0x00]);
+    }
+}
@@ -0,0 +1,142 @@
+//! obstacle - Static segments and boxes between the particles and the ground
+//! Shapes, their responses and the edge geometry for crossing tests
+
+use crate::list::FixedList;
+
+// Upper bound on obstacles, each one has its own output and trigger
+pub const MAX_OBSTACLES: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    // Line from (x0, y0) to (x1, y1)
+    Segment { x0: f32, y0: f32, x1: f32, y1: f32 },
+    // Axis-aligned box from its top-left corner
+    Box { x: f32, y: f32, width: f32, height: f32 },
+}
+
+// What happens to a particle crossing one of the edges
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Response {
+    // Retire the particle, like the ground in retire mode
+    Land,
+    // Carry it along an upward-facing edge until it runs off an end,
+    // other edges deflect
+    Slide,
+    // Reflect its velocity with `ground_restitution`
+    Deflect,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Obstacle {
+    pub shape: Shape,
+    pub response: Response,
+    // Output sent on every hit, `None` derives it from the particle like ground hits
+    pub output: Option<u16>,
+}
+
+impl Obstacle {
+    pub fn edge_count(&self) -> usize {
+        match self.shape {
+            Shape::Segment { .. } => 1,
+            Shape::Box { .. } => 4,
+        }
+    }
+
+    // Edge `n` as [x0, y0, x1, y1], box edges run clockwise from the top
+    pub fn edge(&self, n: usize) -> Option<[f32; 4]> {
+        match self.shape {
+            Shape::Segment { x0, y0, x1, y1 } if n == 0 => Some([x0, y0, x1, y1]),
+            Shape::Box { x, y, width, height } => {
+                let (right, bottom) = (x + width, y + height);
+                match n {
+                    0 => Some([x, y, right, y]),
+                    1 => Some([right, y, right, bottom]),
+                    2 => Some([right, bottom, x, bottom]),
+                    3 => Some([x, bottom, x, y]),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+// Position `t` (0..=1) along the move from `from` to `to` where it crosses
+// `edge`; a move starting on the edge does not cross it
+pub fn crossing(edge: [f32; 4], from: (f32, f32), to: (f32, f32)) -> Option<f32> {
+    let [ax, ay, bx, by] = edge;
+    let (rx, ry) = (to.0 - from.0, to.1 - from.1);
+    let (sx, sy) = (bx - ax, by - ay);
+    let denom = rx * sy - ry * sx;
+    if denom == 0.0 {
+        return None;
+    }
+    let (qx, qy) = (ax - from.0, ay - from.1);
+    let t = (qx * sy - qy * sx) / denom;
+    let u = (qx * ry - qy * rx) / denom;
+    (t > 0.0 && t <= 1.0 && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+// Unit tangent of `edge`, from its first point to its second
+pub fn tangent(edge: [f32; 4]) -> (f32, f32) {
+    let [ax, ay, bx, by] = edge;
+    let (dx, dy) = (bx - ax, by - ay);
+    let len = libm::sqrtf(dx * dx + dy * dy);
+    if len > 0.0 { (dx / len, dy / len) } else { (1.0, 0.0) }
+}
+
+// Unit normal of `edge` on the side of (x, y)
+pub fn normal_towards(edge: [f32; 4], x: f32, y: f32) -> (f32, f32) {
+    let (tx, ty) = tangent(edge);
+    let (nx, ny) = (-ty, tx);
+    if (x - edge[0]) * nx + (y - edge[1]) * ny < 0.0 { (-nx, -ny) } else { (nx, ny) }
+}
+
+// Position of (x, y) along `edge`, 0 at its first point and 1 at its second
+pub fn project(edge: [f32; 4], x: f32, y: f32) -> f32 {
+    let [ax, ay, bx, by] = edge;
+    let (dx, dy) = (bx - ax, by - ay);
+    let len2 = dx * dx + dy * dy;
+    if len2 > 0.0 { ((x - ax) * dx + (y - ay) * dy) / len2 } else { 0.0 }
+}
+
+// Unused slots of the list
+impl Default for Obstacle {
+    fn default() -> Self {
+        Self {
+            shape: Shape::Segment { x0: 0.0, y0: 0.0, x1: 0.0, y1: 0.0 },
+            response: Response::Land,
+            output: None,
+        }
+    }
+}
+
+pub type Obstacles = FixedList<Obstacle, MAX_OBSTACLES>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossing_and_normals() {
+        let ledge = [10.0, 100.0, 50.0, 100.0];
+        assert_eq!(crossing(ledge, (20.0, 98.0), (20.0, 102.0)), Some(0.5));
+        // Outside the ledge, parallel, or starting on it
+        assert_eq!(crossing(ledge, (60.0, 98.0), (60.0, 102.0)), None);
+        assert_eq!(crossing(ledge, (20.0, 98.0), (30.0, 98.0)), None);
+        assert_eq!(crossing(ledge, (20.0, 100.0), (20.0, 103.0)), None);
+
+        assert_eq!(normal_towards(ledge, 20.0, 90.0), (0.0, -1.0));
+        assert_eq!(normal_towards(ledge, 20.0, 110.0), (0.0, 1.0));
+        assert_eq!(project(ledge, 40.0, 99.0), 0.75);
+
+        let block = Obstacle {
+            shape: Shape::Box { x: 0.0, y: 0.0, width: 4.0, height: 2.0 },
+            response: Response::Deflect,
+            output: None,
+        };
+        assert_eq!(block.edge_count(), 4);
+        assert_eq!(block.edge(2), Some([4.0, 2.0, 0.0, 2.0]));
+        assert_eq!(block.edge(4), None);
+    }
+}
@@ -0,0 +1,218 @@
+//! osc - Open Sound Control encoding of particle events
+//! Writes OSC 1.0 messages into caller-provided buffers, no_std compatible
+
+use crate::{ParticleEvent, Side};
+
+// Large enough for every message produced by this module with a short prefix
+pub const MAX_OSC_PACKET: usize = 128;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+}
+
+// CHANGE: Bounds-checked cursor over the output buffer
+// REASON: No heap allocation, overflow is reported instead of panicking
+struct Cursor<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Cursor<'_> {
+    fn push(&mut self, bytes: &[u8]) -> Option<()> {
+        let end = self.len.checked_add(bytes.len())?;
+        self.buf.get_mut(self.len..end)?.copy_from_slice(bytes);
+        self.len = end;
+        Some(())
+    }
+
+    // OSC strings are NUL terminated and padded to four bytes
+    fn push_padded(&mut self, parts: &[&[u8]]) -> Option<()> {
+        for part in parts {
+            self.push(part)?;
+        }
+        self.push(&[0])?;
+        while !self.len.is_multiple_of(4) {
+            self.push(&[0])?;
+        }
+        Some(())
+    }
+}
+
+// Encode `prefix` + `address` with `args`, returns the packet length
+pub fn encode(prefix: &str, address: &str, args: &[OscArg], buf: &mut [u8]) -> Option<usize> {
+    let mut cursor = Cursor { buf, len: 0 };
+    cursor.push_padded(&[prefix.as_bytes(), address.as_bytes()])?;
+
+    cursor.push(b",")?;
+    for arg in args {
+        cursor.push(match arg {
+            OscArg::Int(_) => b"i",
+            OscArg::Float(_) => b"f",
+        })?;
+    }
+    cursor.push_padded(&[])?;
+
+    for arg in args {
+        match *arg {
+            OscArg::Int(v) => cursor.push(&v.to_be_bytes())?,
+            OscArg::Float(v) => cursor.push(&v.to_be_bytes())?,
+        }
+    }
+    Some(cursor.len)
+}
+
+// `<prefix>/ground index output radius time bounce level speed zone floor species`,
+// zone -1 outside every zone, species -1 without one
+// `<prefix>/obstacle index obstacle output radius time`
+// `<prefix>/collision a b output radius time`
+// `<prefix>/merge a b output radius time`
+// `<prefix>/edge index side output radius time`
+// sides and floors: 0 left, 1 right, 2 top, 3 bottom
+pub fn encode_event(prefix: &str, event: &ParticleEvent, buf: &mut [u8]) -> Option<usize> {
+    match *event {
+        ParticleEvent::GroundHit { index, output, radius, time, bounce, level, speed, zone, floor, species } => encode(
+            prefix,
+            "/ground",
+            &[
+                OscArg::Int(index as i32),
+                OscArg::Int(output as i32),
+                OscArg::Float(radius),
+                OscArg::Float(time),
+                OscArg::Int(bounce as i32),
+                OscArg::Float(level),
+                OscArg::Float(speed),
+                OscArg::Int(zone.map_or(-1, |k| k as i32)),
+                OscArg::Int(floor as i32),
+                OscArg::Int(species.map_or(-1, |k| k as i32)),
+            ],
+            buf,
+        ),
+        ParticleEvent::ObstacleHit { index, obstacle, output, radius, time } => encode(
+            prefix,
+            "/obstacle",
+            &[
+                OscArg::Int(index as i32),
+                OscArg::Int(obstacle as i32),
+                OscArg::Int(output as i32),
+                OscArg::Float(radius),
+                OscArg::Float(time),
+            ],
+            buf,
+        ),
+        ParticleEvent::Collision { a, b, output, radius, time } => encode(
+            prefix,
+            "/collision",
+            &[
+                OscArg::Int(a as i32),
+                OscArg::Int(b as i32),
+                OscArg::Int(output as i32),
+                OscArg::Float(radius),
+                OscArg::Float(time),
+            ],
+            buf,
+        ),
+        ParticleEvent::Merge { a, b, output, radius, time } => encode(
+            prefix,
+            "/merge",
+            &[
+                OscArg::Int(a as i32),
+                OscArg::Int(b as i32),
+                OscArg::Int(output as i32),
+                OscArg::Float(radius),
+                OscArg::Float(time),
+            ],
+            buf,
+        ),
+        ParticleEvent::EdgeExit { index, side, output, radius, time } => encode(
+            prefix,
+            "/edge",
+            &[
+                OscArg::Int(index as i32),
+                OscArg::Int(side as i32),
+                OscArg::Int(output as i32),
+                OscArg::Float(radius),
+                OscArg::Float(time),
+            ],
+            buf,
+        ),
+    }
+}
+
+// `<prefix>/outputs ground_output collision_output trigger collision_trigger`
+pub fn encode_outputs(prefix: &str, outputs: (u16, u16, bool, bool), buf: &mut [u8]) -> Option<usize> {
+    let (ground, collision, trigger, collision_trigger) = outputs;
+    encode(
+        prefix,
+        "/outputs",
+        &[
+            OscArg::Int(ground as i32),
+            OscArg::Int(collision as i32),
+            OscArg::Int(trigger as i32),
+            OscArg::Int(collision_trigger as i32),
+        ],
+        buf,
+    )
+}
+
+// CHANGE: Check a prefix against every message before sending any
+// REASON: A long prefix would otherwise drop messages without a warning
+pub fn prefix_fits(prefix: &str) -> bool {
+    let (output, radius, time) = (u16::MAX, 0.0, 0.0);
+    let events = [
+        ParticleEvent::GroundHit {
+            index: 0,
+            output,
+            radius,
+            time,
+            bounce: 0,
+            level: 1.0,
+            speed: 0.0,
+            zone: None,
+            floor: Side::Bottom,
+            species: None,
+        },
+        ParticleEvent::ObstacleHit { index: 0, obstacle: 0, output, radius, time },
+        ParticleEvent::Collision { a: 0, b: 1, output, radius, time },
+        ParticleEvent::Merge { a: 0, b: 1, output, radius, time },
+        ParticleEvent::EdgeExit { index: 0, side: Side::Left, output, radius, time },
+    ];
+    let mut buf = [0u8; MAX_OSC_PACKET];
+    events.iter().all(|event| encode_event(prefix, event, &mut buf).is_some())
+        && encode_outputs(prefix, (0, 0, false, false), &mut buf).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ground_hit_packet() {
+        let mut buf = [0u8; MAX_OSC_PACKET];
+        let event = ParticleEvent::GroundHit { index: 2, output: 1000, radius: 4.0, time: 1.5, bounce: 0, level: 1.0, speed: 8.0, zone: None, floor: Side::Bottom, species: Some(1) };
+        let len = encode_event("/particles", &event, &mut buf).unwrap();
+
+        assert_eq!(&buf[..20], b"/particles/ground\0\0\0");
+        assert_eq!(&buf[20..32], b",iiffiffiii\0");
+        assert_eq!(&buf[32..36], &2i32.to_be_bytes());
+        assert_eq!(&buf[36..40], &1000i32.to_be_bytes());
+        assert_eq!(&buf[40..44], &4.0f32.to_be_bytes());
+        assert_eq!(&buf[44..48], &1.5f32.to_be_bytes());
+        assert_eq!(&buf[48..52], &0i32.to_be_bytes());
+        assert_eq!(&buf[52..56], &1.0f32.to_be_bytes());
+        assert_eq!(&buf[56..60], &8.0f32.to_be_bytes());
+        assert_eq!(&buf[60..64], &(-1i32).to_be_bytes());
+        assert_eq!(&buf[64..68], &3i32.to_be_bytes());
+        assert_eq!(&buf[68..72], &1i32.to_be_bytes());
+        assert_eq!(len, 72);
+    }
+
+    #[test]
+    fn test_small_buffer_is_rejected() {
+        let mut buf = [0u8; 16];
+        assert_eq!(encode_outputs("/particles", (1, 2, true, false), &mut buf), None);
+        assert!(prefix_fits("/particles"));
+        assert!(!prefix_fits("/particles/with/a/very/long/prefix/that/leaves/no/room/for/the/arguments"));
+    }
+}
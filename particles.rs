@@ -5,12 +5,187 @@
 
 // CHANGE: Using heapless for collections
 // REASON: No heap allocation allowed in embedded context
-use heapless::{String, Vec};
+use heapless::{Deque, String, Vec};
 use core::fmt::Write;
 
+// CHANGE: Optional scale quantizer
+// REASON: Pitch output stays out of the core unless requested
+#[cfg(feature = "quantizer")]
+pub mod quantizer;
+
+// CHANGE: MIDI encoding of particle events
+// REASON: Feed the generator straight into a DAW or MIDI device
+pub mod midi;
+
+// CHANGE: OSC encoding of particle events
+// REASON: Drive visuals and SuperCollider patches over the network
+pub mod osc;
+
+// CHANGE: Built-in voice renderer
+// REASON: Audition settings without external synths
+pub mod synth;
+
+// CHANGE: Fixed timestep driver
+// REASON: Behavior must not depend on the host frame rate
+pub mod fixed_step;
+
+// CHANGE: Session record and replay
+// REASON: Get back good-sounding moments bit for bit
+pub mod session;
+
+// CHANGE: Text presets for Settings
+// REASON: Preset libraries instead of editing Settings::default()
+pub mod preset;
+
+// CHANGE: Uniform-grid broadphase for large pools
+// REASON: Selected at compile time with the spatial-hash feature
+#[cfg(feature = "spatial-hash")]
+pub mod broadphase;
+
+// CHANGE: Copy-able fixed-capacity list
+// REASON: Lists inside `Settings` without giving up `Copy`
+pub mod list;
+
+// CHANGE: Static obstacles with their own outputs
+// REASON: Ledges at different heights instead of a single ground line
+pub mod obstacle;
+
+// CHANGE: Noise wind field
+// REASON: Location dependent drift instead of one global sway
+pub mod wind;
+
+// CHANGE: Point attractors and repulsors
+// REASON: Steer where particles land from external CV
+pub mod force;
+
+// CHANGE: Ground zones with their own outputs
+// REASON: Route hits by position, like pads on a drum strip
+pub mod zone;
+
+// CHANGE: Gravity direction and the side acting as the ground
+// REASON: Tilt the world and pour particles into any wall
+pub mod gravity;
+
+// CHANGE: Particle species with their own spawn parameters
+// REASON: Heavy slow drops and light fast sparkles in one scene
+pub mod species;
+
+// CHANGE: Spawn regions with their own rates
+// REASON: Faucets and sweeping sources instead of only the top edge
+pub mod emitter;
+
+use emitter::Emitters;
+use force::ForcePoints;
+use obstacle::{Obstacles, Response, MAX_OBSTACLES};
+use species::{OutputMap, SpeciesTable, MAX_SPECIES};
+use zone::{GroundZones, MAX_GROUND_ZONES};
+
+// CHANGE: Bounded event queue capacity
+// REASON: Every hit must reach the host, not only the latest one
+pub const MAX_EVENTS: usize = 32;
+
+// CHANGE: Typed events pushed by `update`
+// REASON: Replaces polling `get_outputs` for triggers
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParticleEvent {
+    // A particle reached the ground, `bounce` counts its earlier bounces,
+    // `level` (1.0 on the first impact) follows the rebound speed,
+    // `speed` is the impact velocity in px/s, `zone` the ground zone hit,
+    // `floor` the side acting as the ground and `species` the particle's
+    GroundHit {
+        index: usize,
+        output: u16,
+        radius: f32,
+        time: f32,
+        bounce: u8,
+        level: f32,
+        speed: f32,
+        zone: Option<usize>,
+        floor: Side,
+        species: Option<usize>,
+    },
+    // A particle hit `obstacle`; retired, deflected or now sliding along it
+    ObstacleHit { index: usize, obstacle: usize, output: u16, radius: f32, time: f32 },
+    // Two particles collided outside of their cooldown, radius is the larger one
+    Collision { a: usize, b: usize, output: u16, radius: f32, time: f32 },
+    // Two particles merged into `a` instead of colliding, radius is the merged one
+    Merge { a: usize, b: usize, output: u16, radius: f32, time: f32 },
+    // A particle left through a side wall and retired, absorb boundaries only
+    EdgeExit { index: usize, side: Side, output: u16, radius: f32, time: f32 },
+}
+
+// Screen boundary, the bottom one is the ground line
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+// CHANGE: Selectable collision test
+// REASON: Circle overlap with a physical response as an alternative to the original boxes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CollisionMode {
+    // COMPAT: Original AABB test from the top-left corner, no response
+    Box,
+    // Circles around (x, y) with an elastic push-apart response
+    Circle,
+}
+
+// CHANGE: Selectable particle motion
+// REASON: Accelerating falls as an alternative to the original constant speed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Motion {
+    // COMPAT: Constant fall speed and a direct sway offset per update
+    Constant,
+    // Gravity and air drag on `vy`, sway as a force on `vx`
+    Physical,
+}
+
+// COMPAT: The original sway offset was applied once per 60 Hz frame,
+// it is now a velocity scaled by this rate
+pub const SWAY_RATE: f32 = 60.0;
+
+// CHANGE: Type of a merged particle
+// REASON: Coalescence can keep, raise or wrap the scale degree
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MergeTypeRule {
+    // Area weighted average, rounded
+    Average,
+    // Type of the larger particle
+    Max,
+    // Sum wrapped back into 1..=7
+    SumMod,
+}
+
+// CHANGE: Selectable ground behavior
+// REASON: Bouncing particles give ratchets and bouncing-ball rhythms
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GroundMode {
+    // COMPAT: Original behavior, particles retire on the first impact
+    Retire,
+    // Particles rebound with `ground_restitution` until the bounce gets too low
+    Bounce,
+}
+
+// CHANGE: Selectable side edge behavior
+// REASON: Wrap and absorb give different densities for the same settings
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BoundaryMode {
+    // COMPAT: Original behavior, held at the edge while the sway turns back
+    Clamp,
+    // Leave on one side, come back on the other
+    Wrap,
+    // Mirror the position, horizontal velocity and sway
+    Bounce,
+    // Retire the particle with an `EdgeExit` event
+    Absorb,
+}
+
 // CHANGE: Comprehensive settings struct
 // REASON: All configuration externalized for compile-time optimization
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Settings {
     // Pool sizes
     pub max_particles: usize,
@@ -20,8 +195,20 @@ pub struct Settings {
     
     // Physics
     pub gravity: f32,
+    pub gravity_x: f32,              // Gravity direction, normalized on use
+    pub gravity_y: f32,
     pub global_fall_speed: f32,
     pub wind: f32,
+    pub motion: Motion,
+    pub fall_acceleration: f32,       // Physical motion only, px/s²
+    pub terminal_velocity_scale: f32, // Terminal velocity over the constant fall speed
+    
+    // Wind field, off while the strength is zero
+    pub wind_field_strength: f32,    // px/s along the direction
+    pub wind_direction: f32,         // Radians, 0 blows towards +x
+    pub wind_turbulence_scale: f32,  // Size of the gusty patches, px
+    pub wind_drift: f32,             // Speed the patches travel downwind, px/s
+    pub wind_gust: f32,              // 0 = steady, 1 = gusts from calm to double strength
     
     // Timing
     pub collision_cooldown_time: f32,
@@ -34,11 +221,13 @@ pub struct Settings {
     pub ground_level: i32,
     
     // Particle generation
-    pub particle_spawn_chance: f32,
+    pub particle_spawn_rate: f32,    // Average spawns per second
     pub particle_min_size: f32,
     pub particle_max_size: f32,
     pub particle_sway_speed_min: f32,
     pub particle_sway_speed_max: f32,
+    pub species: SpeciesTable,       // Empty = every particle from the ranges above
+    pub emitters: Emitters,          // Spawn on top of particle_spawn_rate
     
     // Dust generation
     pub dust_dx_factor: f32,
@@ -46,9 +235,42 @@ pub struct Settings {
     pub dust_life_min: f32,
     pub dust_life_max: f32,
     pub dust_brightness_max: u8,
+    pub dust_wake_strength: f32,     // Push on specks near a particle, px/s², 0 = off
+    pub dust_wake_radius: f32,       // Reach of the push beyond the particle radius, px
+    pub dust_splash_count: u8,       // Specks kicked up by a ground hit, 0 = off
+    pub dust_splash_speed: f32,      // Launch speed of the splash, px/s
     
     // Output normalization
     pub collision_output_range: f32,
+    pub impact_speed_range: f32,     // Impact speed mapped to the full impact output, px/s
+    
+    // Collisions
+    pub collision_mode: CollisionMode,
+    pub collision_restitution: f32,  // 1.0 = perfectly elastic
+    pub impulse_damping: f32,        // Decay rate of collision velocity, per second
+    pub fragment_count: u8,          // Pieces the larger particle splits into, below 2 = off
+    pub fragment_min_radius: f32,    // Pieces never get smaller than this
+    pub fragment_scatter: f32,       // Outward speed of the pieces, px/s
+    pub collision_merge: bool,       // Merge instead of colliding, wins over fragments
+    pub merge_type_rule: MergeTypeRule,
+    
+    // Ground
+    pub ground_mode: GroundMode,
+    pub ground_restitution: f32,     // Rebound speed over impact speed
+    pub bounce_gravity: f32,         // Pulls bouncing particles back down, px/s²
+    pub bounce_min_height: f32,      // Lower bounces retire the particle, px
+    
+    // Side edges at 0 and screen_width
+    pub boundary_mode: BoundaryMode,
+    
+    // Ground zones, hits outside every zone only drive the ground output
+    pub ground_zones: GroundZones,
+    
+    // Obstacles
+    pub obstacles: Obstacles,
+    
+    // Force points, move them with `update_settings`
+    pub force_points: ForcePoints,
     
     // RNG seed
     pub rng_seed: u32,
@@ -64,25 +286,59 @@ impl Default for Settings {
             max_particles_array: 12,
             max_dust_array: 50,
             gravity: 1.0,
+            gravity_x: 0.0,
+            gravity_y: 1.0,
             global_fall_speed: 5.0,
             wind: 0.1,
+            motion: Motion::Constant,
+            fall_acceleration: 30.0,
+            terminal_velocity_scale: 2.0,
+            wind_field_strength: 0.0,
+            wind_direction: 0.0,
+            wind_turbulence_scale: 80.0,
+            wind_drift: 20.0,
+            wind_gust: 0.5,
             collision_cooldown_time: 3.0,
             trigger_duration: 0.05,
             verbose_duration: 1.0,
             screen_width: 320,
             screen_height: 170,
             ground_level: 150,
-            particle_spawn_chance: 0.2,
+            // COMPAT: About the original 0.2 chance per 60 Hz frame
+            particle_spawn_rate: 13.4,
             particle_min_size: 3.0,
             particle_max_size: 10.0,
             particle_sway_speed_min: 0.1,
             particle_sway_speed_max: 0.3,
+            species: SpeciesTable::new(),
+            emitters: Emitters::new(),
             dust_dx_factor: 10.0,
             dust_dy_max: 5.0,
             dust_life_min: 3.0,
             dust_life_max: 10.0,
             dust_brightness_max: 5,
+            dust_wake_strength: 0.0,
+            dust_wake_radius: 12.0,
+            dust_splash_count: 0,
+            dust_splash_speed: 30.0,
             collision_output_range: 10.0,
+            impact_speed_range: 50.0,
+            collision_mode: CollisionMode::Box,
+            collision_restitution: 1.0,
+            impulse_damping: 2.0,
+            fragment_count: 0,
+            fragment_min_radius: 2.0,
+            fragment_scatter: 15.0,
+            collision_merge: false,
+            merge_type_rule: MergeTypeRule::Average,
+            ground_mode: GroundMode::Retire,
+            ground_restitution: 0.7,
+            bounce_gravity: 20.0,
+            bounce_min_height: 0.05,
+            boundary_mode: BoundaryMode::Clamp,
+            ground_zones: GroundZones::new(),
+            obstacles: Obstacles::new(),
+            force_points: ForcePoints::new(),
             rng_seed: 0x12345678,
         }
     }
@@ -93,6 +349,14 @@ impl Default for Settings {
 pub struct Particle {
     pub x: f32,
     pub y: f32,
+    // CHANGE: Position before the last update
+    // REASON: Renderers interpolate between physics steps
+    pub prev_x: f32,
+    pub prev_y: f32,
+    // CHANGE: Velocity picked up from collision responses
+    // REASON: Colliding particles change course, decays with impulse_damping
+    pub vx: f32,
+    pub vy: f32,
     pub base_speed: f32,
     pub sway: f32,
     pub sway_speed: f32,
@@ -101,7 +365,16 @@ pub struct Particle {
     // CHANGE: Renamed from 'pitch' to 'particle_type'
     // REASON: Domain-agnostic design
     pub particle_type: u8,
+    // CHANGE: Index into `Settings::species`
+    // REASON: Output mapping and channel follow the species
+    pub species: Option<u8>,
     pub last_collision_time: f32,
+    // CHANGE: Ground bounces so far
+    // REASON: Airborne after the first one, and sets the event level
+    pub bounces: u8,
+    // CHANGE: (obstacle, edge) the particle slides along
+    // REASON: Kept on the surface between updates
+    pub surface: Option<(u8, u8)>,
     pub active: bool,
 }
 
@@ -110,18 +383,35 @@ impl Default for Particle {
         Self {
             x: 0.0,
             y: 0.0,
+            prev_x: 0.0,
+            prev_y: 0.0,
+            vx: 0.0,
+            vy: 0.0,
             base_speed: 0.0,
             sway: 0.0,
             sway_speed: 0.0,
             wind_sensitivity: 0.0,
             radius: 0.0,
             particle_type: 0,
+            species: None,
             last_collision_time: 0.0,
+            bounces: 0,
+            surface: None,
             active: false,
         }
     }
 }
 
+impl Particle {
+    // Position blended between the last two steps, `alpha` from `FixedStep::alpha`
+    pub fn interpolated(&self, alpha: f32) -> (f32, f32) {
+        (
+            self.prev_x + (self.x - self.prev_x) * alpha,
+            self.prev_y + (self.y - self.prev_y) * alpha,
+        )
+    }
+}
+
 // Dust speck structure
 #[derive(Copy, Clone)]
 pub struct Dust {
@@ -129,6 +419,10 @@ pub struct Dust {
     pub y: f32,
     pub dx: f32,
     pub dy: f32,
+    // CHANGE: Velocity picked up from wakes and splashes
+    // REASON: Decays with impulse_damping, on top of the drift
+    pub vx: f32,
+    pub vy: f32,
     pub brightness: u8,
     pub life: f32,
     pub active: bool,
@@ -141,6 +435,8 @@ impl Default for Dust {
             y: 0.0,
             dx: 0.0,
             dy: 0.0,
+            vx: 0.0,
+            vy: 0.0,
             brightness: 0,
             life: 0.0,
             active: false,
@@ -167,6 +463,34 @@ pub struct ParticlesSystem<const MAX_PARTICLES: usize, const MAX_DUST: usize> {
     // REASON: Domain-agnostic output values
     pub last_ground_output: u16,
     pub collision_output: u16,
+    // CHANGE: Speed of the last ground impact, normalized by impact_speed_range
+    // REASON: Accelerating falls land harder
+    pub impact_output: u16,
+    
+    // CHANGE: One output and trigger per obstacle
+    // REASON: Each ledge fires its own note
+    pub obstacle_outputs: [u16; MAX_OBSTACLES],
+    pub obstacle_trigger_timers: [f32; MAX_OBSTACLES],
+    
+    // CHANGE: One output and trigger per ground zone
+    // REASON: Separate DAC channels for different parts of the ground
+    pub zone_outputs: [u16; MAX_GROUND_ZONES],
+    pub zone_trigger_timers: [f32; MAX_GROUND_ZONES],
+    
+    // CHANGE: One output and trigger per side, indexed by `Side`
+    // REASON: A tilted world pours into walls that fire their own outputs
+    pub floor_outputs: [u16; 4],
+    pub floor_trigger_timers: [f32; 4],
+    
+    // CHANGE: One output and trigger per species
+    // REASON: Each kind of particle drives its own DAC channel
+    pub species_outputs: [u16; MAX_SPECIES],
+    pub species_trigger_timers: [f32; MAX_SPECIES],
+    
+    // CHANGE: Events since the last `drain_events`
+    // REASON: No hit is lost when several happen in one update
+    events: Deque<ParticleEvent, MAX_EVENTS>,
+    pub dropped_events: u32,
     
     // CHANGE: Using heapless::String for messages
     // REASON: No heap allocation
@@ -196,10 +520,31 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
             verbose_timer: 0.0,
             last_ground_output: 0,
             collision_output: 0,
+            impact_output: 0,
+            obstacle_outputs: [0; MAX_OBSTACLES],
+            obstacle_trigger_timers: [0.0; MAX_OBSTACLES],
+            zone_outputs: [0; MAX_GROUND_ZONES],
+            zone_trigger_timers: [0.0; MAX_GROUND_ZONES],
+            floor_outputs: [0; 4],
+            floor_trigger_timers: [0.0; 4],
+            species_outputs: [0; MAX_SPECIES],
+            species_trigger_timers: [0.0; MAX_SPECIES],
+            events: Deque::new(),
+            dropped_events: 0,
             verbose_message: String::new(),
             verbose: false,
             rng_state: settings.rng_seed,
-            settings,
+            settings: Self::fit_pools(settings),
+        }
+    }
+    
+    // CHANGE: Pool sizes held to the compiled pools
+    // REASON: Presets and replays may ask for more slots than the binary has
+    fn fit_pools(settings: Settings) -> Settings {
+        Settings {
+            max_particles: settings.max_particles.min(MAX_PARTICLES),
+            max_dust: settings.max_dust.min(MAX_DUST),
+            ..settings
         }
     }
     
@@ -222,14 +567,30 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
     // CHANGE: New function to convert position/type to normalized output
     // REASON: Replace domain-specific pitch/voltage conversion
     fn particle_to_output(settings: &Settings, particle: &Particle) -> u16 {
+        // CHANGE: Size range and mapping of the particle's species
+        // REASON: Each species spreads its own sizes over the output
+        let species = particle.species.and_then(|k| settings.species.as_slice().get(k as usize));
+        let (min_size, max_size, map) = match species {
+            Some(s) => (s.min_size.min(s.max_size), s.max_size.max(s.min_size), s.output_map),
+            None => (settings.particle_min_size, settings.particle_max_size, OutputMap::Blend),
+        };
+        
         // COMPAT: Maintains proportional relationship between particle properties and output
-        let position_factor = particle.x / settings.screen_width as f32;
+        // Position along the floor, across the screen when falling down
+        let floor = gravity::floor(gravity::direction(settings));
+        let (along_floor, length) = gravity::along(floor, settings, particle.x, particle.y);
+        let position_factor = along_floor / length;
         let type_factor = particle.particle_type as f32 / 7.0;  // Original had 7 scale degrees
-        let size_factor = (particle.radius - settings.particle_min_size) / 
-                         (settings.particle_max_size - settings.particle_min_size);
+        // A single size sits in the middle
+        let size_factor = if max_size > min_size { (particle.radius - min_size) / (max_size - min_size) } else { 0.5 };
         
         // Combine factors to create output similar to original pitch mapping
-        let combined = (position_factor * 0.3 + type_factor * 0.5 + size_factor * 0.2);
+        let combined = match map {
+            OutputMap::Blend => position_factor * 0.3 + type_factor * 0.5 + size_factor * 0.2,
+            OutputMap::Position => position_factor,
+            OutputMap::Type => type_factor,
+            OutputMap::Size => size_factor,
+        };
         (combined * u16::MAX as f32) as u16
     }
     
@@ -240,8 +601,18 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
         (normalized.clamp(0.0, 1.0) * u16::MAX as f32) as u16
     }
     
-    // Activate a particle
-    fn activate_particle(&mut self) {
+    // CHANGE: Queue an event, dropping the oldest one when full
+    // REASON: A host that drains late still sees the most recent hits
+    fn push_event(&mut self, event: ParticleEvent) {
+        if let Err(event) = self.events.push_back(event) {
+            self.events.pop_front();
+            self.dropped_events = self.dropped_events.wrapping_add(1);
+            let _ = self.events.push_back(event);
+        }
+    }
+    
+    // Activate a particle, from emitter `emitter` or along the spawn side
+    fn activate_particle(&mut self, emitter: Option<usize>) {
         // Find inactive particle
         let mut particle_index = None;
         for i in 0..MAX_PARTICLES {
@@ -252,14 +623,50 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
         }
         
         if let Some(idx) = particle_index {
+            // CHANGE: Pick a species by weight, its ranges replace the global ones
+            // REASON: Several kinds of particle in one scene
+            // COMPAT: No extra random draw while the table is empty
+            let table = self.settings.species;
+            let emitter = emitter.and_then(|e| self.settings.emitters.as_slice().get(e).copied());
+            let species = match emitter.and_then(|e| e.species) {
+                Some(k) => Some(k as usize).filter(|&k| k < table.len()),
+                None if table.is_empty() => None,
+                None => species::pick(table.as_slice(), self.random()),
+            };
+            let (min_size, max_size, min_type, max_type) = match species.map(|k| table.as_slice()[k]) {
+                // Ranges given high end first are taken the other way round
+                Some(s) => {
+                    let (min_type, max_type) = (s.min_type.clamp(1, 7), s.max_type.clamp(1, 7));
+                    (s.min_size.min(s.max_size), s.max_size.max(s.min_size), min_type.min(max_type), max_type.max(min_type))
+                }
+                None => (self.settings.particle_min_size, self.settings.particle_max_size, 1, 7),
+            };
+            let species = species.map(|k| k as u8);
+            let (fall_scale, wind_scale) = species::scales(table.as_slice(), species);
+            
             // Generate random values before mutating the particle
-            let size = self.random_int(
-                self.settings.particle_min_size as i32, 
-                self.settings.particle_max_size as i32
-            ) as f32;
+            let size = self.random_int(min_size as i32, max_size as i32) as f32;
             // COMPAT: Exact same speed calculation as original
-            let speed_factor = (1.5 * size + 3.0) / 10.0 * self.settings.gravity;
-            let x = self.random_range(0.0, self.settings.screen_width as f32);
+            let speed_factor = (1.5 * size + 3.0) / 10.0 * self.settings.gravity * fall_scale;
+            // CHANGE: Spawn along the side opposite to the floor
+            // REASON: Particles fall across the whole screen whatever the gravity
+            let floor = gravity::floor(gravity::direction(&self.settings));
+            let range = if gravity::is_horizontal(floor) { self.settings.screen_width } else { self.settings.ground_level };
+            let (x, y) = match emitter {
+                Some(e) => {
+                    let (u, v) = (self.random(), self.random());
+                    e.region.sample(u, v)
+                }
+                None => {
+                    let lateral = self.random_range(0.0, range as f32);
+                    match floor {
+                        Side::Bottom => (lateral, 0.0),
+                        Side::Top => (lateral, self.settings.ground_level as f32),
+                        Side::Right => (0.0, lateral),
+                        Side::Left => (self.settings.screen_width as f32, lateral),
+                    }
+                }
+            };
             // CHANGE: Using core::f32::consts::PI instead of std
             // REASON: no_std compatibility
             let sway = self.random() * 2.0 * core::f32::consts::PI;
@@ -269,20 +676,34 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
             );
             // CHANGE: particle_type instead of pitch, range 1-7 maintained
             // REASON: Domain-agnostic while maintaining same behavior
-            let particle_type = self.random_int(1, 7) as u8;
+            let particle_type = self.random_int(min_type as i32, max_type as i32) as u8;
+            let (vx, vy) = match emitter {
+                Some(e) => {
+                    let r = self.random();
+                    emitter::launch(&e, r)
+                }
+                None => (0.0, 0.0),
+            };
             
             // Now update the particle
             let p = &mut self.particle_pool[idx];
             p.x = x;
-            p.y = 0.0;
+            p.y = y;
+            p.prev_x = x;
+            p.prev_y = y;
+            p.vx = vx;
+            p.vy = vy;
             p.base_speed = speed_factor;
             p.sway = sway;
             p.sway_speed = sway_speed;
             // COMPAT: Exact same wind sensitivity calculation
-            p.wind_sensitivity = 0.7 + 0.3 / size;
+            p.wind_sensitivity = (0.7 + 0.3 / size) * wind_scale;
             p.radius = size;
             p.particle_type = particle_type;
+            p.species = species;
             p.last_collision_time = self.time - self.settings.collision_cooldown_time;
+            p.bounces = 0;
+            p.surface = None;
             p.active = true;
             self.active_particles += 1;
         }
@@ -314,6 +735,8 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
             d.y = y;
             d.dx = dx;
             d.dy = dy;
+            d.vx = 0.0;
+            d.vy = 0.0;
             d.brightness = brightness;
             d.life = life;
             d.active = true;
@@ -327,30 +750,174 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
     fn update_particles(&mut self, dt: f32) {
         // CHANGE: Fixed-size buffer instead of Vec
         // REASON: Avoid heap allocation
-        let mut particles_to_deactivate: Vec<usize, MAX_PARTICLES> = Vec::new();
+        // Ground and obstacle hits as (index, retire, event)
+        let mut hits: Vec<(usize, bool, ParticleEvent), MAX_PARTICLES> = Vec::new();
+        let fall_speed = self.settings.global_fall_speed;
+        let physical = self.settings.motion == Motion::Physical;
+        // COMPAT: Straight down keeps every step bit for bit identical
+        let g = gravity::direction(&self.settings);
+        let (gx, gy) = g;
+        let floor = gravity::floor(g);
         
         for i in 0..MAX_PARTICLES {
             if self.particle_pool[i].active {
                 let p = &mut self.particle_pool[i];
+                p.prev_x = p.x;
+                p.prev_y = p.y;
                 
-                // Update position - COMPAT: Identical physics
-                p.y += p.base_speed * self.settings.global_fall_speed * dt;
+                // Sway phase at the middle of the step, the drift stays
+                // accurate with coarse steps
+                let phase = p.sway + p.sway_speed * dt * 0.5;
                 p.sway += p.sway_speed * dt;
                 // CHANGE: Using libm::sinf for no_std
                 // REASON: Core doesn't provide trig functions
-                p.x += libm::sinf(p.sway) * self.settings.wind * p.wind_sensitivity * 10.0;
+                let sway = libm::sinf(phase) * self.settings.wind * p.wind_sensitivity * 10.0;
+                if physical {
+                    // CHANGE: Semi-implicit Euler, velocities first
+                    // REASON: Falls accelerate up to a size dependent terminal velocity
+                    // Quadratic drag, treated implicitly so large steps stay stable
+                    let accel = self.settings.fall_acceleration;
+                    let terminal = (p.base_speed * fall_speed * self.settings.terminal_velocity_scale).max(1.0e-3);
+                    let drag = accel / (terminal * terminal);
+                    let (mut along, mut across) = gravity::split((p.vx, p.vy), g);
+                    along = (along + accel * dt) / (1.0 + drag * libm::fabsf(along) * dt);
+                    // COMPAT: Same sway amplitude once the damping has caught up
+                    across += sway * SWAY_RATE * self.settings.impulse_damping * dt;
+                    (p.vx, p.vy) = gravity::join(along, across, g);
+                } else {
+                    // CHANGE: Sway as a velocity instead of a per-update offset
+                    // REASON: The same drift at 60 Hz or on a 1 kHz timer
+                    // Fall along the gravity, sway across it
+                    let fall = p.base_speed * fall_speed;
+                    p.x += fall * gx * dt;
+                    p.y += fall * gy * dt;
+                    let drift = sway * SWAY_RATE;
+                    p.x += drift * gy * dt;
+                    p.y -= drift * gx * dt;
+                }
+                
+                // CHANGE: Push from the wind field at the particle position
+                // REASON: Light particles drift more, and differently across the screen
+                if self.settings.wind_field_strength != 0.0 {
+                    let (wx, wy) = wind::wind_at(&self.settings, self.time, p.x, p.y);
+                    p.x += wx * p.wind_sensitivity * dt;
+                    p.y += wy * p.wind_sensitivity * dt;
+                }
+                
+                // CHANGE: Force points bend the path through the collision velocity
+                // REASON: Damped like collision impulses, so particles settle back to falling
+                if !self.settings.force_points.is_empty() {
+                    let (ax, ay) = force::acceleration(self.settings.force_points.as_slice(), p.x, p.y);
+                    p.vx += ax * dt;
+                    p.vy += ay * dt;
+                }
+                
+                // Velocity from collisions and force points, or all of it with physical motion
+                p.x += p.vx * dt;
+                p.y += p.vy * dt;
+                let damping = libm::expf(-self.settings.impulse_damping * dt);
+                let (mut along, mut across) = gravity::split((p.vx, p.vy), g);
+                across *= damping;
+                // Bouncing particles fly ballistic arcs instead
+                if physical {
+                    // Gravity and drag are already in the velocity
+                } else if p.bounces > 0 {
+                    along += Self::pull(&self.settings) * dt;
+                } else {
+                    along *= damping;
+                }
+                (p.vx, p.vy) = gravity::join(along, across, g);
+                
+                // Handle borders, the two walls next to the floor
+                let (low, high, range) = if gravity::is_horizontal(floor) {
+                    (Side::Left, Side::Right, self.settings.screen_width as f32)
+                } else {
+                    (Side::Top, Side::Bottom, self.settings.ground_level as f32)
+                };
+                let (pos, prev, vel) = if gravity::is_horizontal(floor) {
+                    (&mut p.x, &mut p.prev_x, &mut p.vx)
+                } else {
+                    (&mut p.y, &mut p.prev_y, &mut p.vy)
+                };
+                let side = if *pos < 0.0 {
+                    Some(low)
+                } else if *pos > range {
+                    Some(high)
+                } else {
+                    None
+                };
+                if let Some(side) = side {
+                    let edge = if side == low { 0.0 } else { range };
+                    match self.settings.boundary_mode {
+                        // COMPAT: Held at the edge like the original
+                        BoundaryMode::Clamp => {
+                            // Turn the sway away from the wall once on arrival,
+                            // not on every update it spends held there
+                            let arrived = if side == low { *prev > edge } else { *prev < edge };
+                            *pos = edge;
+                            if arrived {
+                                let (ix, iy) = gravity::inward(side);
+                                p.sway -= (gy * ix - gx * iy) * core::f32::consts::PI / 4.0;
+                            }
+                        }
+                        BoundaryMode::Wrap => {
+                            // The previous position moves along, obstacles
+                            // see a short step instead of one across the screen
+                            let shift = if side == low { range } else { -range };
+                            *pos += shift;
+                            *prev += shift;
+                        }
+                        BoundaryMode::Bounce => {
+                            *pos = 2.0 * edge - *pos;
+                            *vel = -*vel;
+                            // sin(-sway) reverses the sway drift from here on
+                            p.sway = -p.sway;
+                        }
+                        BoundaryMode::Absorb => {
+                            let event = ParticleEvent::EdgeExit {
+                                index: i,
+                                side,
+                                output: Self::particle_to_output(&self.settings, p),
+                                radius: p.radius,
+                                time: self.time,
+                            };
+                            let _ = hits.push((i, true, event));
+                            continue;
+                        }
+                    }
+                }
                 
-                // Handle borders - COMPAT: Identical boundary behavior
-                if p.x < 0.0 {
-                    p.x = 0.0;
-                    p.sway += core::f32::consts::PI / 4.0;
-                } else if p.x > self.settings.screen_width as f32 {
-                    p.x = self.settings.screen_width as f32;
-                    p.sway -= core::f32::consts::PI / 4.0;
+                // CHANGE: Obstacles are checked before the ground
+                // REASON: A particle retired on a ledge never reaches it
+                if let Some((k, retire)) = Self::hit_obstacles(&self.settings, p) {
+                    let output = match self.settings.obstacles.as_slice()[k].output {
+                        Some(output) => output,
+                        None => Self::particle_to_output(&self.settings, p),
+                    };
+                    self.obstacle_outputs[k] = output;
+                    self.obstacle_trigger_timers[k] = self.settings.trigger_duration;
+                    
+                    self.verbose_message.clear();
+                    let _ = write!(
+                        &mut self.verbose_message,
+                        "Obstacle {} Output: {}, Trigger: HIGH",
+                        k, output
+                    );
+                    self.verbose_timer = self.settings.verbose_duration;
+                    
+                    let event = ParticleEvent::ObstacleHit { index: i, obstacle: k, output, radius: p.radius, time: self.time };
+                    let _ = hits.push((i, retire, event));
+                    continue;
                 }
                 
-                // Check ground collision
-                if p.y >= self.settings.ground_level as f32 {
+                // Check ground collision, on whichever side the gravity points at
+                // COMPAT: Retire mode keeps the original test, bounces need a falling particle
+                let (ix, iy) = gravity::inward(floor);
+                let towards = gx * ix + gy * iy;
+                let impact = Self::drift(&self.settings, p) * towards + (p.vx * ix + p.vy * iy);
+                let bouncing = self.settings.ground_mode == GroundMode::Bounce;
+                let reached = gravity::depth(floor, p.x, p.y) >= gravity::level(floor, &self.settings);
+                if reached && (!bouncing || impact > 0.0) {
                     // CHANGE: Generate normalized output instead of MIDI/voltage
                     // REASON: Domain-agnostic design
                     self.last_ground_output = Self::particle_to_output(&self.settings, p);
@@ -367,31 +934,277 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
                     self.verbose_timer = self.settings.verbose_duration;
                     self.trigger_timer = self.settings.trigger_duration;
                     
+                    let range = self.settings.impact_speed_range.max(1.0e-3);
+                    self.impact_output = ((impact / range).clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+                    
+                    // COMPAT: The ground output and trigger fire for every zone and floor
+                    // Zones run along the floor, across the gravity
+                    // A particle clamped onto the far wall sits at the end of the floor, the
+                    // zone lookup excludes that end so it is pulled back inside
+                    let (along_floor, length) = gravity::along(floor, &self.settings, p.x, p.y);
+                    let along_floor = along_floor.clamp(0.0, length * (1.0 - f32::EPSILON));
+                    let zone = zone::zone_at(self.settings.ground_zones.as_slice(), along_floor);
+                    if let Some(k) = zone {
+                        self.zone_outputs[k] = self.last_ground_output;
+                        self.zone_trigger_timers[k] = self.settings.trigger_duration;
+                    }
+                    self.floor_outputs[floor as usize] = self.last_ground_output;
+                    self.floor_trigger_timers[floor as usize] = self.settings.trigger_duration;
+                    let species = p.species.map(|k| k as usize).filter(|&k| k < MAX_SPECIES);
+                    if let Some(k) = species {
+                        self.species_outputs[k] = self.last_ground_output;
+                        self.species_trigger_timers[k] = self.settings.trigger_duration;
+                    }
+                    
+                    let event = ParticleEvent::GroundHit {
+                        index: i,
+                        output: self.last_ground_output,
+                        radius: p.radius,
+                        time: self.time,
+                        bounce: p.bounces,
+                        level: libm::powf(self.settings.ground_restitution, p.bounces as f32),
+                        speed: impact,
+                        zone,
+                        floor,
+                        species,
+                    };
+                    
+                    // CHANGE: Rebound while the next bounce clears the minimum height
+                    // REASON: Each bounce retriggers, the gaps shrink with the energy
+                    let rebound = impact * self.settings.ground_restitution;
+                    let height = rebound * rebound / (2.0 * Self::pull(&self.settings) * towards);
+                    let retire = !(bouncing && height >= self.settings.bounce_min_height);
+                    if !retire {
+                        let speed = -rebound - Self::drift(&self.settings, p) * towards;
+                        gravity::place(floor, &self.settings, p, speed);
+                        p.bounces = p.bounces.saturating_add(1);
+                    }
+                    
                     // PERF: Try to add to deactivation list
-                    let _ = particles_to_deactivate.push(i);
+                    let _ = hits.push((i, retire, event));
+                }
+            }
+        }
+        
+        // Queue hits and deactivate retired particles
+        for &(i, retire, event) in &hits {
+            if let ParticleEvent::GroundHit { radius, floor, .. } = event {
+                let p = self.particle_pool[i];
+                self.splash(floor, p.x, p.y, radius);
+            }
+            self.push_event(event);
+            if retire {
+                self.particle_pool[i].active = false;
+                self.active_particles -= 1;
+            }
+        }
+        
+        let free = self.settings.max_particles.saturating_sub(self.active_particles);
+        for _ in 0..self.spawn_count(self.settings.particle_spawn_rate, dt, free) {
+            self.activate_particle(None);
+        }
+        
+        // CHANGE: Enabled emitters spawn after the spawn side, each at its own rate
+        // REASON: A faucet at one spot next to the spawning across the screen
+        // COMPAT: No random draw without an enabled emitter
+        for e in 0..self.settings.emitters.len() {
+            let emitter = self.settings.emitters.as_slice()[e];
+            if !emitter.enabled {
+                continue;
+            }
+            let free = self.settings.max_particles.saturating_sub(self.active_particles);
+            for _ in 0..self.spawn_count(emitter.rate, dt, free) {
+                self.activate_particle(Some(e));
+            }
+        }
+    }
+    
+    // CHANGE: Poisson number of spawns over dt, from a single draw
+    // REASON: The same spawn rate whatever the update rate
+    fn spawn_count(&mut self, rate: f32, dt: f32, free: usize) -> usize {
+        let mean = rate.max(0.0) * dt;
+        let u = self.random();
+        let (mut term, mut k) = (libm::expf(-mean), 0);
+        let mut cdf = term;
+        while u > cdf && k < free {
+            k += 1;
+            term *= mean / k as f32;
+            cdf += term;
+        }
+        k
+    }
+    
+    // CHANGE: Constant fall speed along the gravity, on top of the velocity
+    // REASON: Zero with physical motion, where the velocity is the whole motion
+    fn drift(settings: &Settings, p: &Particle) -> f32 {
+        match settings.motion {
+            Motion::Constant => p.base_speed * settings.global_fall_speed,
+            Motion::Physical => 0.0,
+        }
+    }
+    
+    // Acceleration bringing rebounds back down, kept positive so every
+    // rebound height is finite and every bounce comes back
+    fn pull(settings: &Settings) -> f32 {
+        let pull = match settings.motion {
+            Motion::Constant => settings.bounce_gravity,
+            Motion::Physical => settings.fall_acceleration,
+        };
+        pull.max(1.0e-3)
+    }
+    
+    // CHANGE: Move a particle along or off the obstacles
+    // REASON: Returns the obstacle hit and whether the particle retires
+    fn hit_obstacles(settings: &Settings, p: &mut Particle) -> Option<(usize, bool)> {
+        let obstacles = settings.obstacles.as_slice();
+        let (gx, gy) = gravity::direction(settings);
+        
+        // Keep sliding particles on their edge, drop them past either end
+        if let Some((k, e)) = p.surface {
+            match obstacles.get(k as usize).and_then(|o| o.edge(e as usize)) {
+                Some(edge) => {
+                    // Slide edges face up, so the normal is the one against the gravity
+                    let (tx, ty) = obstacle::tangent(edge);
+                    let (nx, ny) = if ty * gx - tx * gy <= 0.0 { (ty, -tx) } else { (-ty, tx) };
+                    let (dx, dy) = (p.x - p.prev_x, p.y - p.prev_y);
+                    if dx * nx + dy * ny <= 0.0 {
+                        let along = dx * tx + dy * ty;
+                        p.x = p.prev_x + along * tx;
+                        p.y = p.prev_y + along * ty;
+                        let v = p.vx * tx + p.vy * ty;
+                        p.vx = v * tx;
+                        p.vy = v * ty;
+                    }
+                    let u = obstacle::project(edge, p.x, p.y);
+                    if !(0.0..=1.0).contains(&u) || dx * nx + dy * ny > 0.0 {
+                        p.surface = None;
+                    }
                 }
+                None => p.surface = None,
             }
         }
         
-        // Deactivate particles
-        for &i in &particles_to_deactivate {
-            self.particle_pool[i].active = false;
-            self.active_particles -= 1;
+        // Earliest edge crossed by this update's move
+        let from = (p.prev_x, p.prev_y);
+        let mut first: Option<(usize, usize, f32)> = None;
+        for (k, o) in obstacles.iter().enumerate() {
+            for e in 0..o.edge_count() {
+                if p.surface == Some((k as u8, e as u8)) { continue; }
+                let Some(edge) = o.edge(e) else { continue };
+                if let Some(t) = obstacle::crossing(edge, from, (p.x, p.y)) {
+                    if first.is_none_or(|(_, _, best)| t < best) {
+                        first = Some((k, e, t));
+                    }
+                }
+            }
         }
+        let (k, e, t) = first?;
+        let edge = obstacles[k].edge(e)?;
         
-        // Spawn new particles - COMPAT: Same spawn logic
-        if self.active_particles < self.settings.max_particles && 
-           self.random() > (1.0 - self.settings.particle_spawn_chance) {
-            self.activate_particle();
+        // Stop at the edge, a hair on the side the particle came from
+        let (nx, ny) = obstacle::normal_towards(edge, from.0, from.1);
+        p.x = from.0 + (p.x - from.0) * t + nx * 1.0e-3;
+        p.y = from.1 + (p.y - from.1) * t + ny * 1.0e-3;
+        
+        match obstacles[k].response {
+            Response::Land => Some((k, true)),
+            // Only upward-facing edges can be slid on
+            Response::Slide if nx * gx + ny * gy < 0.0 => {
+                let (tx, ty) = obstacle::tangent(edge);
+                let v = p.vx * tx + p.vy * ty;
+                p.vx = v * tx;
+                p.vy = v * ty;
+                p.surface = Some((k as u8, e as u8));
+                Some((k, false))
+            }
+            Response::Slide | Response::Deflect => {
+                // Only the velocity is reflected, wind sway alone just gets blocked
+                let drift = Self::drift(settings, p);
+                let approach = (p.vx + drift * gx) * nx + (p.vy + drift * gy) * ny;
+                if approach >= 0.0 {
+                    return None;
+                }
+                // COMPAT: Same cutoff as ground bounces, slower hits land
+                // instead of resting on the edge with the fall pushing into it
+                let rebound = -approach * settings.ground_restitution;
+                let height = rebound * rebound / (2.0 * Self::pull(settings));
+                if height < settings.bounce_min_height {
+                    return Some((k, true));
+                }
+                let push = (1.0 + settings.ground_restitution) * approach;
+                p.vx -= push * nx;
+                p.vy -= push * ny;
+                Some((k, false))
+            }
+        }
+    }
+    
+    // CHANGE: Kick dust up where a particle hits the ground
+    // REASON: Impacts should be visible in the dust field
+    // PERF: Reuses the specks closest to the end of their life, the pool stays as is
+    fn splash(&mut self, floor: Side, x: f32, y: f32, radius: f32) {
+        let level = gravity::level(floor, &self.settings);
+        let (ix, iy) = gravity::inward(floor);
+        // Along the floor, and the contact point on it
+        let (ax, ay) = (libm::fabsf(iy), libm::fabsf(ix));
+        let (cx, cy) = if gravity::is_horizontal(floor) { (x, level * iy) } else { (level * ix, y) };
+        for n in 0..self.settings.dust_splash_count {
+            let mut oldest = None;
+            let mut oldest_life = f32::MAX;
+            for (k, d) in self.dust_pool.iter().enumerate() {
+                // Specks of this splash sit at the launch height and are skipped
+                if d.active && d.life < oldest_life && gravity::depth(floor, d.x, d.y) < level - 0.5 {
+                    oldest = Some(k);
+                    oldest_life = d.life;
+                }
+            }
+            let Some(k) = oldest else { return };
+            
+            // Fan out upwards, alternating sides
+            let side = if n % 2 == 0 { 1.0 } else { -1.0 };
+            let spread = self.random_range(0.2, 1.0) * side;
+            let speed = self.settings.dust_splash_speed * self.random_range(0.5, 1.0);
+            let life = self.random_range(self.settings.dust_life_min, self.settings.dust_life_max);
+            let d = &mut self.dust_pool[k];
+            d.x = cx + spread * radius * 0.5 * ax - 0.5 * ix;
+            d.y = cy + spread * radius * 0.5 * ay - 0.5 * iy;
+            d.dx = 0.0;
+            d.dy = 0.0;
+            d.vx = spread * speed * ax - speed * ix;
+            d.vy = spread * speed * ay - speed * iy;
+            d.life = life;
         }
     }
     
     // Update dust
     fn update_dust(&mut self, dt: f32) {
+        let wake = self.settings.dust_wake_strength;
+        let damping = libm::expf(-self.settings.impulse_damping * dt);
+        let centered = self.settings.collision_mode == CollisionMode::Circle;
         for d in &mut self.dust_pool {
             if d.active {
-                d.x += d.dx * dt;
-                d.y += d.dy * dt;
+                // CHANGE: Particles push nearby specks aside
+                // REASON: Falling particles leave a visible wake
+                if wake != 0.0 {
+                    for p in self.particle_pool.iter().filter(|p| p.active) {
+                        // Box particles are drawn from their top-left corner
+                        let offset = if centered { 0.0 } else { p.radius * 0.5 };
+                        let (ox, oy) = (d.x - p.x - offset, d.y - p.y - offset);
+                        let reach = p.radius + self.settings.dust_wake_radius;
+                        let d2 = ox * ox + oy * oy;
+                        if d2 < reach * reach && d2 > 0.0 {
+                            let dist = libm::sqrtf(d2);
+                            let push = wake * (1.0 - dist / reach) * dt / dist;
+                            d.vx += ox * push;
+                            d.vy += oy * push;
+                        }
+                    }
+                }
+                
+                d.x += (d.dx + d.vx) * dt;
+                d.y += (d.dy + d.vy) * dt;
+                d.vx *= damping;
+                d.vy *= damping;
                 d.life -= dt;
                 
                 if d.life <= 0.0 {
@@ -402,63 +1215,290 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
         }
         
         // Spawn new dust - COMPAT: Same spawn logic
+        // Only expired specks are replaced, so dust spawns at
+        // `max_dust / mean life` per second whatever the update rate
+        // Never past the compiled pool, a full pool has no slot to fill
         while self.active_dust < self.settings.max_particles * 8 && 
-              self.active_dust < self.settings.max_dust {
+              self.active_dust < self.settings.max_dust.min(MAX_DUST) {
             self.activate_dust();
         }
     }
     
+    fn overlaps(&self, p1: &Particle, p2: &Particle) -> bool {
+        match self.settings.collision_mode {
+            // Box collision detection - COMPAT: Identical collision logic
+            CollisionMode::Box => {
+                p1.x < p2.x + p2.radius &&
+                p1.x + p1.radius > p2.x &&
+                p1.y < p2.y + p2.radius &&
+                p1.y + p1.radius > p2.y
+            }
+            CollisionMode::Circle => {
+                let dx = p2.x - p1.x;
+                let dy = p2.y - p1.y;
+                let r = p1.radius + p2.radius;
+                dx * dx + dy * dy < r * r
+            }
+        }
+    }
+    
+    // CHANGE: Elastic response between two overlapping circles
+    // REASON: Collisions should look like what they sound like
+    fn resolve_contact(&mut self, i: usize, j: usize) {
+        let restitution = self.settings.collision_restitution;
+        let (p1, p2) = (self.particle_pool[i], self.particle_pool[j]);
+        
+        let dx = p2.x - p1.x;
+        let dy = p2.y - p1.y;
+        let dist = libm::sqrtf(dx * dx + dy * dy);
+        let (nx, ny) = if dist > 0.0 { (dx / dist, dy / dist) } else { (1.0, 0.0) };
+        
+        // Mass follows the area
+        let m1 = p1.radius * p1.radius;
+        let m2 = p2.radius * p2.radius;
+        let total = m1 + m2;
+        
+        // Push apart along the normal, the lighter particle moves more
+        let overlap = p1.radius + p2.radius - dist;
+        let a = &mut self.particle_pool[i];
+        a.x -= nx * overlap * m2 / total;
+        a.y -= ny * overlap * m2 / total;
+        let b = &mut self.particle_pool[j];
+        b.x += nx * overlap * m1 / total;
+        b.y += ny * overlap * m1 / total;
+        
+        // Exchange momentum only while approaching
+        let (gx, gy) = gravity::direction(&self.settings);
+        let (drift1, drift2) = (Self::drift(&self.settings, &p1), Self::drift(&self.settings, &p2));
+        let (v1x, v1y) = (p1.vx + drift1 * gx, p1.vy + drift1 * gy);
+        let (v2x, v2y) = (p2.vx + drift2 * gx, p2.vy + drift2 * gy);
+        let approach = (v2x - v1x) * nx + (v2y - v1y) * ny;
+        if approach < 0.0 {
+            let impulse = -(1.0 + restitution) * approach / (1.0 / m1 + 1.0 / m2);
+            let a = &mut self.particle_pool[i];
+            a.vx -= impulse / m1 * nx;
+            a.vy -= impulse / m1 * ny;
+            let b = &mut self.particle_pool[j];
+            b.vx += impulse / m2 * nx;
+            b.vy += impulse / m2 * ny;
+        }
+    }
+    
     // Check collisions
     fn check_collisions(&mut self) {
+        // CHANGE: Optional uniform-grid broadphase
+        // REASON: The O(n²) pair loop does not scale to 256+ particles
+        // COMPAT: Pairs are visited in the same (i, j) order as the full loop,
+        // the grid is rebuilt whenever a pair moves or adds particles
+        #[cfg(feature = "spatial-hash")]
+        {
+            let (width, height) = (self.settings.screen_width as f32, self.settings.screen_height as f32);
+            let mut grid = broadphase::Grid::build(&self.particle_pool, width, height);
+            let mut hits: Vec<u16, MAX_PARTICLES> = Vec::new();
+            for i in 0..MAX_PARTICLES {
+                // Candidates of i after `from`, the ones up to it are done
+                let mut from = i;
+                while self.particle_pool[i].active {
+                    // PERF: Narrowphase first, only the overlapping pairs get ordered
+                    hits.clear();
+                    let pool = &self.particle_pool;
+                    grid.for_each_candidate(i, |j| {
+                        if j > from && self.overlaps(&pool[i], &pool[j]) {
+                            broadphase::insert_sorted(&mut hits, j as u16);
+                        }
+                    });
+                    let changed = hits.iter().position(|&j| self.collide_pair(i, j as usize));
+                    match changed {
+                        Some(k) => {
+                            from = hits[k] as usize;
+                            grid = broadphase::Grid::build(&self.particle_pool, width, height);
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+        
+        #[cfg(not(feature = "spatial-hash"))]
+        self.check_all_pairs();
+    }
+    
+    // COMPAT: Original pair loop, the grid is tested against it
+    #[cfg(any(test, not(feature = "spatial-hash")))]
+    fn check_all_pairs(&mut self) {
         for i in 0..MAX_PARTICLES {
             if !self.particle_pool[i].active { continue; }
             
             for j in (i + 1)..MAX_PARTICLES {
-                if !self.particle_pool[j].active { continue; }
-                
-                let p1 = self.particle_pool[i];
-                let p2 = self.particle_pool[j];
-                
-                // Box collision detection - COMPAT: Identical collision logic
-                if p1.x < p2.x + p2.radius &&
-                   p1.x + p1.radius > p2.x &&
-                   p1.y < p2.y + p2.radius &&
-                   p1.y + p1.radius > p2.y 
-                {
-                    // Check cooldown
-                    if self.time - p1.last_collision_time >= self.settings.collision_cooldown_time &&
-                       self.time - p2.last_collision_time >= self.settings.collision_cooldown_time 
-                    {
-                        // CHANGE: Generate normalized collision output
-                        // REASON: Domain-agnostic design
-                        let collision_value = self.random_range(
-                            -self.settings.collision_output_range / 2.0, 
-                            self.settings.collision_output_range / 2.0
-                        );
-                        self.collision_output = self.collision_to_output(collision_value);
-                        
-                        // CHANGE: Format message using heapless
-                        // REASON: No heap allocation
-                        self.verbose_message.clear();
-                        let _ = write!(
-                            &mut self.verbose_message,
-                            "Collision Output: {}, Trigger: HIGH", 
-                            self.collision_output
-                        );
-                        
-                        self.verbose_timer = self.settings.verbose_duration;
-                        self.collision_trigger_timer = self.settings.trigger_duration;
-                        
-                        self.particle_pool[i].last_collision_time = self.time;
-                        self.particle_pool[j].last_collision_time = self.time;
-                    }
-                }
+                self.collide_pair(i, j);
+            }
+        }
+    }
+    
+    // Returns whether particles were moved, merged or added
+    fn collide_pair(&mut self, i: usize, j: usize) -> bool {
+        if !self.particle_pool[i].active || !self.particle_pool[j].active { return false; }
+        
+        // PERF: Test in place, copy only the overlapping pairs
+        if !self.overlaps(&self.particle_pool[i], &self.particle_pool[j]) { return false; }
+        
+        let p1 = self.particle_pool[i];
+        let p2 = self.particle_pool[j];
+        
+        let circle = self.settings.collision_mode == CollisionMode::Circle;
+        if circle {
+            self.resolve_contact(i, j);
+        }
+        
+        // Check cooldown
+        if self.time - p1.last_collision_time >= self.settings.collision_cooldown_time &&
+           self.time - p2.last_collision_time >= self.settings.collision_cooldown_time 
+        {
+            // CHANGE: Generate normalized collision output
+            // REASON: Domain-agnostic design
+            let collision_value = self.random_range(
+                -self.settings.collision_output_range / 2.0, 
+                self.settings.collision_output_range / 2.0
+            );
+            self.collision_output = self.collision_to_output(collision_value);
+            
+            // CHANGE: Format message using heapless
+            // REASON: No heap allocation
+            self.verbose_message.clear();
+            let _ = write!(
+                &mut self.verbose_message,
+                "Collision Output: {}, Trigger: HIGH", 
+                self.collision_output
+            );
+            
+            self.verbose_timer = self.settings.verbose_duration;
+            self.collision_trigger_timer = self.settings.trigger_duration;
+            
+            self.particle_pool[i].last_collision_time = self.time;
+            self.particle_pool[j].last_collision_time = self.time;
+            
+            if self.settings.collision_merge {
+                let radius = self.merge(i, j);
+                self.push_event(ParticleEvent::Merge { a: i, b: j, output: self.collision_output, radius, time: self.time });
+                return true;
+            }
+            
+            self.push_event(ParticleEvent::Collision {
+                a: i,
+                b: j,
+                output: self.collision_output,
+                radius: p1.radius.max(p2.radius),
+                time: self.time,
+            });
+            
+            if self.settings.fragment_count > 1 {
+                self.fragment(if p2.radius > p1.radius { j } else { i });
+                return true;
             }
         }
+        circle
+    }
+    
+    // CHANGE: Merge particle `j` into `i`, returns the new radius
+    // REASON: Raindrop coalescence, fewer but bigger particles
+    // COMPAT: Area and the momentum of collision pushes (vx, vy) are kept; fall
+    // speed and wind sensitivity follow the spawn formula for the new size and
+    // the sway carries on from `i`, so the merged drift is not conserved
+    fn merge(&mut self, i: usize, j: usize) -> f32 {
+        let (p1, p2) = (self.particle_pool[i], self.particle_pool[j]);
+        let (m1, m2) = (p1.radius * p1.radius, p2.radius * p2.radius);
+        let total = m1 + m2;
+        let radius = libm::sqrtf(total);
+        // The merged particle stays of the larger one's species
+        let species = if m2 > m1 { p2.species } else { p1.species };
+        let (fall_scale, wind_scale) = species::scales(self.settings.species.as_slice(), species);
+        let particle_type = match self.settings.merge_type_rule {
+            MergeTypeRule::Average => {
+                ((p1.particle_type as f32 * m1 + p2.particle_type as f32 * m2) / total + 0.5) as u8
+            }
+            MergeTypeRule::Max => if m2 > m1 { p2.particle_type } else { p1.particle_type },
+            MergeTypeRule::SumMod => (p1.particle_type + p2.particle_type + 6) % 7 + 1,
+        };
+        
+        let p = &mut self.particle_pool[i];
+        p.x = (p1.x * m1 + p2.x * m2) / total;
+        p.y = (p1.y * m1 + p2.y * m2) / total;
+        p.prev_x = (p1.prev_x * m1 + p2.prev_x * m2) / total;
+        p.prev_y = (p1.prev_y * m1 + p2.prev_y * m2) / total;
+        p.vx = (p1.vx * m1 + p2.vx * m2) / total;
+        p.vy = (p1.vy * m1 + p2.vy * m2) / total;
+        p.base_speed = (1.5 * radius + 3.0) / 10.0 * self.settings.gravity * fall_scale;
+        p.wind_sensitivity = (0.7 + 0.3 / radius) * wind_scale;
+        p.radius = radius;
+        p.particle_type = particle_type;
+        p.species = species;
+        p.surface = None;
+        
+        self.particle_pool[j].active = false;
+        self.active_particles -= 1;
+        radius
+    }
+    
+    // CHANGE: Split a particle into smaller, faster pieces from free slots
+    // REASON: Collision cascades within the fixed pool
+    // COMPAT: Area is shared out, speed grows as the radius shrinks and the
+    // type climbs one step per generation
+    fn fragment(&mut self, i: usize) {
+        let parent = self.particle_pool[i];
+        // Pieces need free slots and room under max_particles, like spawns
+        let free = self.particle_pool.iter().filter(|p| !p.active).count();
+        let room = free.min(self.settings.max_particles.saturating_sub(self.active_particles));
+        let min_radius = self.settings.fragment_min_radius.max(0.5);
+        let by_size = (parent.radius * parent.radius / (min_radius * min_radius)) as usize;
+        let pieces = (self.settings.fragment_count as usize).min(room + 1).min(by_size);
+        if pieces < 2 {
+            return;
+        }
+        
+        let radius = parent.radius / libm::sqrtf(pieces as f32);
+        let (_, wind_scale) = species::scales(self.settings.species.as_slice(), parent.species);
+        let phase = self.random() * 2.0 * core::f32::consts::PI;
+        let mut slot = 0;
+        for n in 0..pieces {
+            // The parent slot holds the first piece
+            let k = if n == 0 {
+                i
+            } else {
+                while self.particle_pool[slot].active { slot += 1; }
+                self.active_particles += 1;
+                slot
+            };
+            let angle = phase + n as f32 * 2.0 * core::f32::consts::PI / pieces as f32;
+            let (dir_y, dir_x) = (libm::sinf(angle), libm::cosf(angle));
+            let (x, y) = (parent.x + dir_x * radius, parent.y + dir_y * radius);
+            self.particle_pool[k] = Particle {
+                x,
+                y,
+                // No motion before the split, renderers interpolate from here
+                prev_x: x,
+                prev_y: y,
+                vx: parent.vx + dir_x * self.settings.fragment_scatter,
+                vy: parent.vy + dir_y * self.settings.fragment_scatter,
+                base_speed: parent.base_speed * parent.radius / radius,
+                wind_sensitivity: (0.7 + 0.3 / radius) * wind_scale,
+                radius,
+                particle_type: (parent.particle_type + 1).min(7),
+                // Pieces start in cooldown, so they cannot split each other at once
+                last_collision_time: self.time,
+                surface: None,
+                ..parent
+            };
+        }
     }
     
     // Update system
     pub fn update(&mut self, dt: f32) {
+        self.advance(dt);
+        self.check_collisions();
+    }
+    
+    // Everything in `update` before the collision checks
+    fn advance(&mut self, dt: f32) {
         self.time += dt;
         
         // Update timers
@@ -471,10 +1511,16 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
         if self.collision_trigger_timer > 0.0 {
             self.collision_trigger_timer -= dt;
         }
+        let timers = self.obstacle_trigger_timers.iter_mut().chain(&mut self.zone_trigger_timers);
+        let timers = timers.chain(&mut self.floor_trigger_timers).chain(&mut self.species_trigger_timers);
+        for timer in timers {
+            if *timer > 0.0 {
+                *timer -= dt;
+            }
+        }
         
         self.update_particles(dt);
         self.update_dust(dt);
-        self.check_collisions();
     }
     
     // CHANGE: Get current outputs as normalized values
@@ -488,10 +1534,49 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
         )
     }
     
+    // Output and trigger state of obstacle `k`
+    pub fn get_obstacle_output(&self, k: usize) -> (u16, bool) {
+        match (self.obstacle_outputs.get(k), self.obstacle_trigger_timers.get(k)) {
+            (Some(&output), Some(&timer)) => (output, timer > 0.0),
+            _ => (0, false),
+        }
+    }
+    
+    // Output and trigger state of ground hits on `side`
+    pub fn get_floor_output(&self, side: Side) -> (u16, bool) {
+        (self.floor_outputs[side as usize], self.floor_trigger_timers[side as usize] > 0.0)
+    }
+    
+    // Output and trigger state of ground zone `k`
+    pub fn get_zone_output(&self, k: usize) -> (u16, bool) {
+        match (self.zone_outputs.get(k), self.zone_trigger_timers.get(k)) {
+            (Some(&output), Some(&timer)) => (output, timer > 0.0),
+            _ => (0, false),
+        }
+    }
+    
+    // Output and trigger state of ground hits by species `k`
+    pub fn get_species_output(&self, k: usize) -> (u16, bool) {
+        match (self.species_outputs.get(k), self.species_trigger_timers.get(k)) {
+            (Some(&output), Some(&timer)) => (output, timer > 0.0),
+            _ => (0, false),
+        }
+    }
+    
+    // CHANGE: Drain queued events in the order they happened
+    // REASON: Hosts turn every hit into a note instead of polling
+    pub fn drain_events(&mut self) -> impl Iterator<Item = ParticleEvent> + '_ {
+        core::iter::from_fn(move || self.events.pop_front())
+    }
+    
     // CHANGE: Update settings at runtime if needed
     // REASON: Support dynamic reconfiguration
     pub fn update_settings(&mut self, settings: Settings) {
-        self.settings = settings;
+        self.settings = Self::fit_pools(settings);
+    }
+    
+    pub fn settings(&self) -> &Settings {
+        &self.settings
     }
 }
 
@@ -501,9 +1586,576 @@ impl<const MAX_PARTICLES: usize, const MAX_DUST: usize> ParticlesSystem<MAX_PART
 mod tests {
     use super::*;
     
+    // System with `particles` in its first slots, nothing spawning and no dust
+    fn placed<const N: usize>(settings: Settings, particles: &[Particle]) -> ParticlesSystem<N, 1> {
+        let mut system = ParticlesSystem::new(Settings { max_particles: N, max_dust: 0, particle_spawn_rate: 0.0, ..settings });
+        for (slot, p) in system.particle_pool.iter_mut().zip(particles) {
+            *slot = Particle { active: true, ..*p };
+        }
+        system.active_particles = particles.len().min(N);
+        system
+    }
+    
     #[test]
     fn test_particle_system_creation() {
         let settings = Settings::default();
         let _system: ParticlesSystem<12, 50> = ParticlesSystem::new(settings);
     }
+    
+    #[test]
+    fn test_pool_sizes_past_the_compiled_pools() {
+        // Used to refill dust forever once every slot was taken
+        let settings = Settings { max_particles: 12, max_dust: 100, ..Settings::default() };
+        let mut system: ParticlesSystem<4, 8> = ParticlesSystem::new(settings);
+        system.update(1.0 / 60.0);
+        assert_eq!(system.active_dust, 8);
+        assert_eq!((system.settings().max_particles, system.settings().max_dust), (4, 8));
+        
+        system.update_settings(Settings { max_dust: 50, ..settings });
+        system.update(1.0 / 60.0);
+        assert_eq!(system.settings().max_dust, 8);
+    }
+    
+    #[test]
+    fn test_every_ground_hit_is_queued() {
+        let mut system: ParticlesSystem<12, 50> = ParticlesSystem::new(Settings::default());
+        for i in 0..4 {
+            system.particle_pool[i] = Particle {
+                x: 40.0 + 60.0 * i as f32,
+                y: 149.5,
+                base_speed: 1.0,
+                radius: 4.0,
+                particle_type: 3,
+                active: true,
+                ..Particle::default()
+            };
+        }
+        system.active_particles = 4;
+        
+        system.update(0.1);
+        
+        let hits = system
+            .drain_events()
+            .filter(|e| matches!(e, ParticleEvent::GroundHit { .. }))
+            .count();
+        assert_eq!(hits, 4);
+        assert_eq!(system.drain_events().count(), 0);
+    }
+    
+    #[test]
+    fn test_ground_zones_route_hits() {
+        let mut settings = Settings::default();
+        let _ = settings.ground_zones.push(zone::GroundZone { x0: 0.0, x1: 120.0, note: Some(36) });
+        let _ = settings.ground_zones.push(zone::GroundZone { x0: 120.0, x1: 200.0, note: None });
+        let particles: [Particle; 4] = core::array::from_fn(|i| Particle {
+            x: 40.0 + 60.0 * i as f32,
+            y: 149.5,
+            base_speed: 1.0,
+            radius: 4.0,
+            ..Particle::default()
+        });
+        let mut system: ParticlesSystem<12, 1> = placed(settings, &particles);
+        
+        system.update(0.1);
+        
+        let mut zones: Vec<Option<usize>, 4> = Vec::new();
+        for event in system.drain_events() {
+            if let ParticleEvent::GroundHit { zone, .. } = event {
+                let _ = zones.push(zone);
+            }
+        }
+        assert_eq!(zones.as_slice(), &[Some(0), Some(0), Some(1), None]);
+        assert!(system.get_zone_output(0).1 && system.get_zone_output(1).1);
+        assert!(!system.get_zone_output(2).1);
+        assert!(system.get_outputs().2);
+        
+        // A particle clamped onto the right edge lands in the zone ending there
+        let mut settings = Settings::default();
+        let _ = settings.ground_zones.push(zone::GroundZone { x0: 200.0, x1: 320.0, note: None });
+        let mut system: ParticlesSystem<1, 1> =
+            placed(settings, &[Particle { x: 320.0, y: 149.5, base_speed: 1.0, radius: 4.0, ..Particle::default() }]);
+        system.update(0.1);
+        assert!(matches!(system.drain_events().next(), Some(ParticleEvent::GroundHit { zone: Some(0), .. })));
+    }
+    
+    #[test]
+    fn test_bounces_ratchet_then_retire() {
+        let settings = Settings { ground_mode: GroundMode::Bounce, ..Settings::default() };
+        let mut system: ParticlesSystem<1, 1> =
+            placed(settings, &[Particle { x: 100.0, y: 149.9, base_speed: 1.2, radius: 6.0, ..Particle::default() }]);
+        
+        let mut hits: Vec<(f32, u8, f32), 16> = Vec::new();
+        for _ in 0..600 {
+            system.update(1.0 / 120.0);
+            for event in system.drain_events() {
+                if let ParticleEvent::GroundHit { time, bounce, level, .. } = event {
+                    let _ = hits.push((time, bounce, level));
+                }
+            }
+        }
+        
+        assert!(hits.len() >= 3);
+        assert!(!system.particle_pool[0].active);
+        for (n, w) in hits.windows(3).enumerate() {
+            assert_eq!(w[0].1 as usize, n);
+            assert!(w[2].0 - w[1].0 < w[1].0 - w[0].0);
+            assert!(w[1].2 < w[0].2);
+        }
+    }
+    
+    #[test]
+    fn test_obstacles_land_and_slide() {
+        let mut settings = Settings::default();
+        let _ = settings.obstacles.push(obstacle::Obstacle {
+            shape: obstacle::Shape::Segment { x0: 0.0, y0: 80.0, x1: 100.0, y1: 80.0 },
+            response: Response::Land,
+            output: Some(1000),
+        });
+        let _ = settings.obstacles.push(obstacle::Obstacle {
+            shape: obstacle::Shape::Segment { x0: 150.0, y0: 60.0, x1: 250.0, y1: 100.0 },
+            response: Response::Slide,
+            output: None,
+        });
+        let mut system: ParticlesSystem<2, 1> = placed(settings, &[
+            Particle { x: 50.0, y: 79.0, base_speed: 1.0, radius: 4.0, ..Particle::default() },
+            Particle { x: 170.0, y: 67.0, base_speed: 3.0, radius: 4.0, ..Particle::default() },
+        ]);
+        
+        system.update(0.3);
+        let events: Vec<ParticleEvent, 4> = system.drain_events().collect();
+        assert!(matches!(events[0], ParticleEvent::ObstacleHit { index: 0, obstacle: 0, output: 1000, .. }));
+        assert!(matches!(events[1], ParticleEvent::ObstacleHit { index: 1, obstacle: 1, .. }));
+        assert!(!system.particle_pool[0].active);
+        assert_eq!(system.get_obstacle_output(0), (1000, true));
+        
+        // Slides down the slope without retriggering, then drops off its lower end
+        let start = system.particle_pool[1].x;
+        for _ in 0..2000 {
+            system.update(1.0 / 60.0);
+            if system.particle_pool[1].surface.is_none() { break; }
+        }
+        let p = system.particle_pool[1];
+        assert!(p.active && p.x > start && p.x >= 249.0);
+        assert!(system.drain_events().all(|e| !matches!(e, ParticleEvent::ObstacleHit { .. })));
+    }
+    
+    #[test]
+    fn test_slow_deflect_hits_land() {
+        let mut settings = Settings::default();
+        let _ = settings.obstacles.push(obstacle::Obstacle {
+            shape: obstacle::Shape::Segment { x0: 50.0, y0: 100.0, x1: 150.0, y1: 100.0 },
+            response: Response::Deflect,
+            output: None,
+        });
+        let mut system: ParticlesSystem<1, 1> =
+            placed(settings, &[Particle { x: 100.0, y: 95.0, base_speed: 1.0, radius: 4.0, ..Particle::default() }]);
+        
+        // Rebounds shrink until one is too low to count, that hit retires it
+        let mut hits = 0;
+        for _ in 0..6000 {
+            system.update(1.0 / 60.0);
+            hits += system.drain_events().filter(|e| matches!(e, ParticleEvent::ObstacleHit { .. })).count();
+            if system.active_particles == 0 { break; }
+        }
+        assert_eq!(system.active_particles, 0);
+        assert!(hits > 1);
+    }
+    
+    #[test]
+    fn test_collision_fragments_larger_particle() {
+        let small = Particle { x: 100.0, y: 50.0, base_speed: 1.0, radius: 4.0, particle_type: 2, last_collision_time: -10.0, ..Particle::default() };
+        let big = Particle { x: 102.0, y: 52.0, prev_x: 90.0, prev_y: 40.0, base_speed: 1.5, radius: 9.0, particle_type: 6, last_collision_time: -10.0, ..Particle::default() };
+        let settings = Settings { fragment_count: 3, ..Settings::default() };
+        let mut system: ParticlesSystem<6, 1> = placed(settings, &[small, big]);
+        system.particle_pool[3].active = true;
+        system.active_particles = 3;
+        
+        system.check_collisions();
+        
+        // The big one became three pieces, the small one is untouched
+        assert_eq!(system.active_particles, 5);
+        assert_eq!(system.particle_pool[0].radius, 4.0);
+        for k in [1, 2, 4] {
+            let p = system.particle_pool[k];
+            assert!(p.active && (p.radius - 9.0 / libm::sqrtf(3.0)).abs() < 1.0e-4);
+            assert!(p.base_speed > 1.5 && p.particle_type == 7);
+            assert!(p.prev_x == p.x && p.prev_y == p.y);
+        }
+        assert!(!system.particle_pool[5].active);
+        
+        // Free slots past max_particles stay free
+        let mut system: ParticlesSystem<6, 1> = placed(settings, &[small, big]);
+        system.settings.max_particles = 3;
+        system.check_collisions();
+        assert_eq!(system.active_particles, 3);
+        assert_eq!(system.particle_pool.iter().filter(|p| p.active).count(), 3);
+    }
+    
+    #[test]
+    fn test_collision_merges_by_area() {
+        let settings = Settings { collision_merge: true, merge_type_rule: MergeTypeRule::SumMod, ..Settings::default() };
+        let mut system: ParticlesSystem<2, 1> = placed(settings, &[
+            Particle { x: 100.0, y: 50.0, vx: 4.0, radius: 3.0, particle_type: 5, last_collision_time: -10.0, ..Particle::default() },
+            Particle { x: 102.0, y: 51.0, radius: 4.0, particle_type: 6, last_collision_time: -10.0, ..Particle::default() },
+        ]);
+        
+        system.check_collisions();
+        
+        let p = system.particle_pool[0];
+        assert_eq!(p.radius, 5.0);
+        assert_eq!(p.particle_type, 4);
+        assert!((p.vx - 4.0 * 9.0 / 25.0).abs() < 1.0e-5);
+        assert!(!system.particle_pool[1].active && system.active_particles == 1);
+        assert!(matches!(
+            system.drain_events().next(),
+            Some(ParticleEvent::Merge { a: 0, b: 1, radius, .. }) if radius == 5.0
+        ));
+    }
+    
+    #[test]
+    fn test_dust_wake_and_splash() {
+        let settings = Settings {
+            max_dust: 4,
+            max_particles: 1,
+            dust_wake_strength: 200.0,
+            dust_splash_count: 2,
+            particle_spawn_rate: 0.0,
+            ..Settings::default()
+        };
+        let mut system: ParticlesSystem<1, 4> = ParticlesSystem::new(settings);
+        system.update(0.01);
+        system.particle_pool[0] = Particle { x: 100.0, y: 60.0, radius: 4.0, base_speed: 1.0, active: true, ..Particle::default() };
+        system.active_particles = 1;
+        system.dust_pool[0] = Dust { x: 108.0, y: 62.0, life: 5.0, active: true, ..Dust::default() };
+        
+        // Right of the particle center, so pushed further right
+        system.update(0.01);
+        assert!(system.dust_pool[0].vx > 0.0 && system.dust_pool[0].x > 108.0);
+        
+        system.particle_pool[0].y = 149.9;
+        system.update(0.1);
+        let kicked = system.dust_pool.iter().filter(|d| d.vy < 0.0 && d.y > 140.0).count();
+        assert_eq!(kicked, 2);
+    }
+    
+    #[test]
+    fn test_physical_motion_accelerates_to_terminal_velocity() {
+        let settings = Settings { motion: Motion::Physical, wind: 0.0, ..Settings::default() };
+        let mut system: ParticlesSystem<1, 1> =
+            placed(settings, &[Particle { x: 100.0, base_speed: 1.5, radius: 8.0, ..Particle::default() }]);
+        
+        let terminal = 1.5 * settings.global_fall_speed * settings.terminal_velocity_scale;
+        let mut last_step = 0.0;
+        for n in 0..120 {
+            system.update(1.0 / 60.0);
+            let p = system.particle_pool[0];
+            let step = p.y - p.prev_y;
+            if n < 10 {
+                assert!(step > last_step);
+            }
+            last_step = step;
+            assert!(p.vy <= terminal);
+        }
+        assert!((system.particle_pool[0].vy - terminal).abs() < 0.05 * terminal);
+        
+        // The impact speed reaches the events and the impact output
+        system.particle_pool[0].y = 149.9;
+        system.update(1.0 / 60.0);
+        let speed = match system.drain_events().next() {
+            Some(ParticleEvent::GroundHit { speed, .. }) => speed,
+            other => panic!("{:?}", other),
+        };
+        assert!((speed - terminal).abs() < 0.05 * terminal);
+        let expected = speed / settings.impact_speed_range * u16::MAX as f32;
+        assert!((system.impact_output as f32 - expected).abs() < 2.0);
+    }
+    
+    #[test]
+    fn test_boundary_modes() {
+        let mut settings = Settings { wind: 0.0, ..Settings::default() };
+        let mut ends = [(0.0, 0.0, true); 4];
+        let modes = [BoundaryMode::Clamp, BoundaryMode::Wrap, BoundaryMode::Bounce, BoundaryMode::Absorb];
+        for (end, mode) in ends.iter_mut().zip(modes) {
+            settings.boundary_mode = mode;
+            // Two pixels to the right in one step, one past the edge
+            let mut system: ParticlesSystem<1, 1> =
+                placed(settings, &[Particle { x: 319.0, y: 50.0, vx: 120.0, radius: 4.0, ..Particle::default() }]);
+            system.update(1.0 / 60.0);
+            let p = system.particle_pool[0];
+            *end = (p.x, p.vx, p.active);
+            if mode == BoundaryMode::Absorb {
+                assert!(matches!(
+                    system.drain_events().next(),
+                    Some(ParticleEvent::EdgeExit { index: 0, side: Side::Right, .. })
+                ));
+                assert_eq!(system.active_particles, 0);
+            }
+        }
+        let [clamp, wrap, bounce, absorb] = ends;
+        assert_eq!(clamp.0, 320.0);
+        assert!((wrap.0 - 1.0).abs() < 1.0e-3 && wrap.1 > 0.0);
+        assert!((bounce.0 - 319.0).abs() < 1.0e-3 && bounce.1 < 0.0);
+        assert!(!absorb.2);
+    }
+    
+    #[test]
+    fn test_species_spawn_from_their_own_ranges() {
+        let mut settings = Settings { max_particles: 12, max_dust: 0, particle_spawn_rate: 600.0, ..Settings::default() };
+        let bass = species::Species {
+            weight: 1.0,
+            min_size: 9.0,
+            max_size: 10.0,
+            min_type: 1,
+            max_type: 1,
+            fall_speed: 0.5,
+            wind_sensitivity: 0.2,
+            output_map: OutputMap::Size,
+            channel: Some(4),
+        };
+        let sparkle = species::Species { min_size: 2.0, max_size: 2.0, min_type: 7, fall_speed: 3.0, ..species::Species::default() };
+        let _ = settings.species.push(bass);
+        let _ = settings.species.push(sparkle);
+        let mut system: ParticlesSystem<12, 1> = ParticlesSystem::new(settings);
+        system.update(0.1);
+        
+        let mut seen = [false; 2];
+        for p in system.particle_pool.iter().filter(|p| p.active) {
+            match p.species {
+                Some(0) => {
+                    assert!(p.radius >= 9.0 && p.particle_type == 1);
+                    assert_eq!(p.base_speed, (1.5 * p.radius + 3.0) / 10.0 * 0.5);
+                    seen[0] = true;
+                }
+                Some(1) => {
+                    assert!(p.radius == 2.0 && p.particle_type == 7);
+                    assert_eq!(p.wind_sensitivity, 0.7 + 0.3 / 2.0);
+                    seen[1] = true;
+                }
+                _ => panic!("particle without a species"),
+            }
+        }
+        assert_eq!(seen, [true, true]);
+        
+        // A ground hit drives the species output, mapped over the species sizes
+        let mut system: ParticlesSystem<12, 1> =
+            placed(settings, &[Particle { x: 100.0, y: 149.5, base_speed: 1.0, radius: 10.0, species: Some(0), ..Particle::default() }]);
+        system.update(0.1);
+        assert!(matches!(
+            system.drain_events().next(),
+            Some(ParticleEvent::GroundHit { species: Some(0), output: u16::MAX, .. })
+        ));
+        assert_eq!(system.get_species_output(0), (u16::MAX, true));
+        assert!(!system.get_species_output(1).1);
+        
+        // Ranges given high end first still spawn inside them
+        let mut settings = Settings { max_particles: 12, max_dust: 0, particle_spawn_rate: 600.0, ..Settings::default() };
+        let inverted = species::Species { min_size: 6.0, max_size: 4.0, min_type: 5, max_type: 3, output_map: OutputMap::Size, ..species::Species::default() };
+        let _ = settings.species.push(inverted);
+        let mut system: ParticlesSystem<12, 1> = ParticlesSystem::new(settings);
+        system.update(0.1);
+        assert_eq!(system.active_particles, 12);
+        for p in system.particle_pool.iter() {
+            assert!((4.0..=6.0).contains(&p.radius) && (3..=5).contains(&p.particle_type));
+        }
+        let largest = Particle { radius: 6.0, species: Some(0), ..Particle::default() };
+        assert_eq!(ParticlesSystem::<1, 1>::particle_to_output(&settings, &largest), u16::MAX);
+        
+        // A single-size species still blends position and type
+        settings.species = SpeciesTable::new();
+        let _ = settings.species.push(sparkle);
+        let left = Particle { x: 40.0, y: 149.5, radius: 2.0, particle_type: 7, species: Some(0), ..Particle::default() };
+        let right = Particle { x: 280.0, ..left };
+        let (left, right) = (
+            ParticlesSystem::<1, 1>::particle_to_output(&settings, &left),
+            ParticlesSystem::<1, 1>::particle_to_output(&settings, &right),
+        );
+        assert!(left > 0 && right > left);
+    }
+    
+    #[test]
+    fn test_emitters_spawn_at_their_region() {
+        let mut settings = Settings { max_particles: 12, max_dust: 0, particle_spawn_rate: 0.0, ..Settings::default() };
+        let _ = settings.species.push(species::Species::default());
+        let _ = settings.species.push(species::Species { min_size: 2.0, max_size: 2.0, ..species::Species::default() });
+        let faucet = emitter::Emitter {
+            region: emitter::Region::Point { x: 200.0, y: 30.0 },
+            rate: 600.0,
+            vx: 5.0,
+            vy: 20.0,
+            species: Some(1),
+            ..emitter::Emitter::default()
+        };
+        let _ = settings.emitters.push(faucet);
+        let _ = settings.emitters.push(emitter::Emitter { enabled: false, ..faucet });
+        let mut system: ParticlesSystem<12, 1> = ParticlesSystem::new(settings);
+        system.update(0.1);
+        
+        assert_eq!(system.active_particles, 12);
+        for p in system.particle_pool.iter() {
+            assert_eq!((p.x, p.y, p.vx, p.vy), (200.0, 30.0, 5.0, 20.0));
+            assert!(p.species == Some(1) && p.radius == 2.0);
+        }
+        
+        // Closed emitters spawn nothing
+        settings.emitters.as_mut_slice()[0].enabled = false;
+        let mut system: ParticlesSystem<12, 1> = ParticlesSystem::new(settings);
+        system.update(0.1);
+        assert_eq!(system.active_particles, 0);
+    }
+    
+    #[test]
+    fn test_tilted_gravity_pours_into_wall() {
+        let mut settings = Settings { wind: 0.0, ..Settings::default() };
+        gravity::set_angle(&mut settings, core::f32::consts::FRAC_PI_2);
+        let mut system: ParticlesSystem<1, 1> =
+            placed(settings, &[Particle { x: 319.5, y: 50.0, base_speed: 1.0, radius: 4.0, ..Particle::default() }]);
+        
+        system.update(0.1);
+        
+        assert!(matches!(
+            system.drain_events().next(),
+            Some(ParticleEvent::GroundHit { index: 0, floor: Side::Right, zone: None, .. })
+        ));
+        assert!(system.get_floor_output(Side::Right).1);
+        assert!(!system.get_floor_output(Side::Bottom).1);
+        assert_eq!(system.active_particles, 0);
+        
+        // Positions run down the wall, like the zones
+        let _ = settings.species.push(species::Species { output_map: OutputMap::Position, ..species::Species::default() });
+        let p = Particle { x: 320.0, y: 75.0, species: Some(0), ..Particle::default() };
+        assert_eq!(ParticlesSystem::<1, 1>::particle_to_output(&settings, &p), u16::MAX / 2);
+    }
+    
+    // Ground hits, collisions and dust respawns over `seconds` at a fixed dt
+    fn event_counts(dt: f32, seconds: f32) -> (u32, u32, u32) {
+        let settings = Settings {
+            max_particles: 32,
+            max_dust: 16,
+            particle_spawn_rate: 1.5,
+            global_fall_speed: 20.0,
+            ..Settings::default()
+        };
+        let mut system: ParticlesSystem<32, 16> = ParticlesSystem::new(settings);
+        let (mut ground, mut collisions, mut dust) = (0, 0, 0);
+        let mut lives = [0.0; 16];
+        for _ in 0..libm::roundf(seconds / dt) as u32 {
+            system.update(dt);
+            for event in system.drain_events() {
+                match event {
+                    ParticleEvent::GroundHit { .. } => ground += 1,
+                    ParticleEvent::Collision { .. } => collisions += 1,
+                    _ => {}
+                }
+            }
+            // A speck whose life went up was respawned
+            for (d, life) in system.dust_pool.iter().zip(lives.iter_mut()) {
+                if d.life > *life {
+                    dust += 1;
+                }
+                *life = d.life;
+            }
+        }
+        (ground, collisions, dust)
+    }
+    
+    #[test]
+    fn test_event_rates_match_across_dt() {
+        let seconds = 300.0;
+        let (ground, collisions, dust) = event_counts(1.0 / 60.0, seconds);
+        // Every spawn lands, apart from the last few still falling
+        assert!(ground as f32 > 0.9 * 1.5 * seconds && (ground as f32) < 1.1 * 1.5 * seconds);
+        assert!(collisions > 20);
+        
+        let close = |a: u32, b: u32, tolerance: f32| (a as f32 - b as f32).abs() <= tolerance * b as f32;
+        for dt in [1.0 / 30.0, 1.0 / 240.0, 1.0 / 1000.0] {
+            let (g, c, d) = event_counts(dt, seconds);
+            assert!(close(g, ground, 0.1), "dt {}: {} ground hits, {} at 60 Hz", dt, g, ground);
+            assert!(close(c, collisions, 0.3), "dt {}: {} collisions, {} at 60 Hz", dt, c, collisions);
+            assert!(close(d, dust, 0.05), "dt {}: {} dust spawns, {} at 60 Hz", dt, d, dust);
+        }
+    }
+    
+    #[test]
+    fn test_sway_drift_matches_across_dt() {
+        let settings = Settings { wind: 0.3, ..Settings::default() };
+        let sway = Particle { x: 100.0, base_speed: 1.0, radius: 4.0, sway_speed: 2.0, wind_sensitivity: 1.0, ..Particle::default() };
+        let mut ends = [0.0; 4];
+        let mut held = [0.0; 4];
+        for ((end, held), dt) in ends.iter_mut().zip(&mut held).zip([1.0 / 30.0, 1.0 / 60.0, 1.0 / 240.0, 1.0 / 1000.0]) {
+            let mut system: ParticlesSystem<1, 1> = placed(settings, &[sway]);
+            // Drifts into the right wall and stays there until the sway turns back
+            let mut wall: ParticlesSystem<1, 1> = placed(settings, &[Particle { x: 280.0, ..sway }]);
+            for _ in 0..libm::roundf(2.0 / dt) as u32 {
+                system.update(dt);
+            }
+            *end = system.particle_pool[0].x;
+            for _ in 0..libm::roundf(3.0 / dt) as u32 {
+                wall.update(dt);
+                if wall.particle_pool[0].x >= 320.0 {
+                    *held += dt;
+                }
+            }
+        }
+        // Drifts of about 150 px agree to a tenth of a pixel
+        assert!(ends[1] - 100.0 > 20.0);
+        for end in ends {
+            assert!((end - ends[1]).abs() < 0.1, "{:?}", ends);
+        }
+        // Held for over a second whatever the step
+        assert!(held[1] > 1.0);
+        for time in held {
+            assert!((time - held[1]).abs() < 0.05, "{:?}", held);
+        }
+    }
+    
+    #[test]
+    #[cfg(feature = "spatial-hash")]
+    fn test_grid_matches_all_pairs_with_circles_and_fragments() {
+        let settings = Settings {
+            max_particles: 64,
+            max_dust: 0,
+            particle_spawn_rate: 120.0,
+            particle_min_size: 4.0,
+            particle_max_size: 12.0,
+            collision_mode: CollisionMode::Circle,
+            collision_cooldown_time: 0.2,
+            fragment_count: 3,
+            ..Settings::default()
+        };
+        let mut grid: ParticlesSystem<64, 1> = ParticlesSystem::new(settings);
+        let mut all_pairs: ParticlesSystem<64, 1> = ParticlesSystem::new(settings);
+        let mut collisions = 0;
+        for _ in 0..1200 {
+            grid.update(1.0 / 60.0);
+            all_pairs.advance(1.0 / 60.0);
+            all_pairs.check_all_pairs();
+            loop {
+                let (a, b) = (grid.drain_events().next(), all_pairs.drain_events().next());
+                assert_eq!(a, b);
+                match a {
+                    Some(ParticleEvent::Collision { .. }) => collisions += 1,
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+        }
+        assert!(collisions > 50);
+    }
+    
+    #[test]
+    fn test_circle_collision_pushes_apart() {
+        let settings = Settings { collision_mode: CollisionMode::Circle, ..Settings::default() };
+        let mut system: ParticlesSystem<2, 1> = placed(settings, &[
+            Particle { x: 100.0, y: 50.0, radius: 5.0, last_collision_time: -10.0, ..Particle::default() },
+            Particle { x: 107.0, y: 50.0, vx: -20.0, radius: 5.0, last_collision_time: -10.0, ..Particle::default() },
+        ]);
+        
+        system.check_collisions();
+        
+        let (a, b) = (system.particle_pool[0], system.particle_pool[1]);
+        assert!(b.x - a.x >= 10.0 - 1.0e-4);
+        // Equal masses exchange their velocities
+        assert!((a.vx + 20.0).abs() < 1.0e-3 && b.vx.abs() < 1.0e-3);
+        assert!(matches!(system.drain_events().next(), Some(ParticleEvent::Collision { a: 0, b: 1, .. })));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,592 @@
+//! preset - Text preset format for `Settings`
+//! One `key = value` line per field, `#` starts a comment, no_std compatible
+//! Obstacles take one `obstacle = shape x y x1|width y1|height response [output]` line each,
+//! force points one `force = attract|repel x y strength falloff` line each,
+//! ground zones one `ground_zone = x0 x1 [note]` line each, species one
+//! `species = weight min_size max_size min_type max_type fall_speed wind_sensitivity map [channel]` line each
+//! and emitters one `emitter = point x y|segment x0 y0 x1 y1|box x y width height rate vx vy spread [species] [off]` line each
+
+use core::fmt::{self, Write};
+
+use crate::emitter::{Emitter, Emitters, Region, MAX_EMITTERS};
+use crate::fixed_step::DEFAULT_FIXED_STEP;
+use crate::gravity;
+use crate::force::{ForcePoint, ForcePoints, Polarity};
+use crate::obstacle::{Obstacle, Obstacles, Response, Shape};
+use crate::species::{OutputMap, Species, SpeciesTable};
+use crate::zone::{GroundZone, GroundZones};
+use crate::{BoundaryMode, CollisionMode, GroundMode, MergeTypeRule, Motion, Settings};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PresetErrorKind {
+    MissingEquals,
+    UnknownKey,
+    InvalidValue,
+}
+
+// Error with the 1-based line it was found on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PresetError {
+    pub line: usize,
+    pub kind: PresetErrorKind,
+}
+
+// CHANGE: Keys are the `Settings` field names
+// REASON: Stable, self-describing and easy to edit by hand
+pub fn write_preset(settings: &Settings, out: &mut impl Write) -> fmt::Result {
+    let s = settings;
+    writeln!(out, "# particles preset")?;
+    writeln!(out, "max_particles = {}", s.max_particles)?;
+    writeln!(out, "max_dust = {}", s.max_dust)?;
+    writeln!(out, "max_particles_array = {}", s.max_particles_array)?;
+    writeln!(out, "max_dust_array = {}", s.max_dust_array)?;
+    writeln!(out, "gravity = {}", s.gravity)?;
+    writeln!(out, "gravity_x = {}", s.gravity_x)?;
+    writeln!(out, "gravity_y = {}", s.gravity_y)?;
+    writeln!(out, "global_fall_speed = {}", s.global_fall_speed)?;
+    writeln!(out, "wind = {}", s.wind)?;
+    writeln!(out, "motion = {}", match s.motion {
+        Motion::Constant => "constant",
+        Motion::Physical => "physical",
+    })?;
+    writeln!(out, "fall_acceleration = {}", s.fall_acceleration)?;
+    writeln!(out, "terminal_velocity_scale = {}", s.terminal_velocity_scale)?;
+    writeln!(out, "wind_field_strength = {}", s.wind_field_strength)?;
+    writeln!(out, "wind_direction = {}", s.wind_direction)?;
+    writeln!(out, "wind_turbulence_scale = {}", s.wind_turbulence_scale)?;
+    writeln!(out, "wind_drift = {}", s.wind_drift)?;
+    writeln!(out, "wind_gust = {}", s.wind_gust)?;
+    writeln!(out, "collision_cooldown_time = {}", s.collision_cooldown_time)?;
+    writeln!(out, "trigger_duration = {}", s.trigger_duration)?;
+    writeln!(out, "verbose_duration = {}", s.verbose_duration)?;
+    writeln!(out, "screen_width = {}", s.screen_width)?;
+    writeln!(out, "screen_height = {}", s.screen_height)?;
+    writeln!(out, "ground_level = {}", s.ground_level)?;
+    writeln!(out, "particle_spawn_rate = {}", s.particle_spawn_rate)?;
+    writeln!(out, "particle_min_size = {}", s.particle_min_size)?;
+    writeln!(out, "particle_max_size = {}", s.particle_max_size)?;
+    writeln!(out, "particle_sway_speed_min = {}", s.particle_sway_speed_min)?;
+    writeln!(out, "particle_sway_speed_max = {}", s.particle_sway_speed_max)?;
+    for species in s.species.as_slice() {
+        write_species(species, out)?;
+    }
+    for emitter in s.emitters.as_slice() {
+        write_emitter(emitter, out)?;
+    }
+    writeln!(out, "dust_dx_factor = {}", s.dust_dx_factor)?;
+    writeln!(out, "dust_dy_max = {}", s.dust_dy_max)?;
+    writeln!(out, "dust_life_min = {}", s.dust_life_min)?;
+    writeln!(out, "dust_life_max = {}", s.dust_life_max)?;
+    writeln!(out, "dust_brightness_max = {}", s.dust_brightness_max)?;
+    writeln!(out, "dust_wake_strength = {}", s.dust_wake_strength)?;
+    writeln!(out, "dust_wake_radius = {}", s.dust_wake_radius)?;
+    writeln!(out, "dust_splash_count = {}", s.dust_splash_count)?;
+    writeln!(out, "dust_splash_speed = {}", s.dust_splash_speed)?;
+    writeln!(out, "collision_output_range = {}", s.collision_output_range)?;
+    writeln!(out, "impact_speed_range = {}", s.impact_speed_range)?;
+    writeln!(out, "collision_mode = {}", match s.collision_mode {
+        CollisionMode::Box => "box",
+        CollisionMode::Circle => "circle",
+    })?;
+    writeln!(out, "collision_restitution = {}", s.collision_restitution)?;
+    writeln!(out, "impulse_damping = {}", s.impulse_damping)?;
+    writeln!(out, "fragment_count = {}", s.fragment_count)?;
+    writeln!(out, "fragment_min_radius = {}", s.fragment_min_radius)?;
+    writeln!(out, "fragment_scatter = {}", s.fragment_scatter)?;
+    writeln!(out, "collision_merge = {}", s.collision_merge)?;
+    writeln!(out, "merge_type_rule = {}", match s.merge_type_rule {
+        MergeTypeRule::Average => "average",
+        MergeTypeRule::Max => "max",
+        MergeTypeRule::SumMod => "summod",
+    })?;
+    writeln!(out, "ground_mode = {}", match s.ground_mode {
+        GroundMode::Retire => "retire",
+        GroundMode::Bounce => "bounce",
+    })?;
+    writeln!(out, "ground_restitution = {}", s.ground_restitution)?;
+    writeln!(out, "bounce_gravity = {}", s.bounce_gravity)?;
+    writeln!(out, "bounce_min_height = {}", s.bounce_min_height)?;
+    writeln!(out, "boundary_mode = {}", match s.boundary_mode {
+        BoundaryMode::Clamp => "clamp",
+        BoundaryMode::Wrap => "wrap",
+        BoundaryMode::Bounce => "bounce",
+        BoundaryMode::Absorb => "absorb",
+    })?;
+    for zone in s.ground_zones.as_slice() {
+        write!(out, "ground_zone = {} {}", zone.x0, zone.x1)?;
+        if let Some(note) = zone.note {
+            write!(out, " {}", note)?;
+        }
+        writeln!(out)?;
+    }
+    for o in s.obstacles.as_slice() {
+        write_obstacle(o, out)?;
+    }
+    for point in s.force_points.as_slice() {
+        let polarity = match point.polarity {
+            Polarity::Attract => "attract",
+            Polarity::Repel => "repel",
+        };
+        writeln!(out, "force = {} {} {} {} {}", polarity, point.x, point.y, point.strength, point.falloff)?;
+    }
+    writeln!(out, "rng_seed = 0x{:08x}", s.rng_seed)?;
+    Ok(())
+}
+
+fn write_obstacle(o: &Obstacle, out: &mut impl Write) -> fmt::Result {
+    match o.shape {
+        Shape::Segment { x0, y0, x1, y1 } => write!(out, "obstacle = segment {} {} {} {}", x0, y0, x1, y1)?,
+        Shape::Box { x, y, width, height } => write!(out, "obstacle = box {} {} {} {}", x, y, width, height)?,
+    }
+    write!(out, " {}", match o.response {
+        Response::Land => "land",
+        Response::Slide => "slide",
+        Response::Deflect => "deflect",
+    })?;
+    if let Some(output) = o.output {
+        write!(out, " {}", output)?;
+    }
+    writeln!(out)
+}
+
+fn write_species(s: &Species, out: &mut impl Write) -> fmt::Result {
+    write!(
+        out,
+        "species = {} {} {} {} {} {} {}",
+        s.weight, s.min_size, s.max_size, s.min_type, s.max_type, s.fall_speed, s.wind_sensitivity
+    )?;
+    write!(out, " {}", match s.output_map {
+        OutputMap::Blend => "blend",
+        OutputMap::Position => "position",
+        OutputMap::Type => "type",
+        OutputMap::Size => "size",
+    })?;
+    if let Some(channel) = s.channel {
+        write!(out, " {}", channel)?;
+    }
+    writeln!(out)
+}
+
+fn parse_species(value: &str) -> Option<Species> {
+    let mut words = value.split_whitespace();
+    let mut sizes = [0.0f32; 3];
+    for n in &mut sizes {
+        *n = words.next()?.parse().ok()?;
+    }
+    let [weight, min_size, max_size] = sizes;
+    if min_size > max_size {
+        return None;
+    }
+    let mut types = [0u8; 2];
+    for t in &mut types {
+        *t = words.next()?.parse().ok().filter(|t| (1..=7).contains(t))?;
+    }
+    let [min_type, max_type] = types;
+    if min_type > max_type {
+        return None;
+    }
+    let fall_speed = words.next()?.parse().ok()?;
+    let wind_sensitivity = words.next()?.parse().ok()?;
+    let output_map = match words.next()? {
+        "blend" => OutputMap::Blend,
+        "position" => OutputMap::Position,
+        "type" => OutputMap::Type,
+        "size" => OutputMap::Size,
+        _ => return None,
+    };
+    let channel = match words.next() {
+        Some(word) => Some(word.parse::<u8>().ok().filter(|&channel| channel < 16)?),
+        None => None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some(Species { weight, min_size, max_size, min_type, max_type, fall_speed, wind_sensitivity, output_map, channel })
+}
+
+fn write_emitter(e: &Emitter, out: &mut impl Write) -> fmt::Result {
+    match e.region {
+        Region::Point { x, y } => write!(out, "emitter = point {} {}", x, y)?,
+        Region::Segment { x0, y0, x1, y1 } => write!(out, "emitter = segment {} {} {} {}", x0, y0, x1, y1)?,
+        Region::Box { x, y, width, height } => write!(out, "emitter = box {} {} {} {}", x, y, width, height)?,
+    }
+    write!(out, " {} {} {} {}", e.rate, e.vx, e.vy, e.spread)?;
+    if let Some(species) = e.species {
+        write!(out, " {}", species)?;
+    }
+    if !e.enabled {
+        write!(out, " off")?;
+    }
+    writeln!(out)
+}
+
+fn parse_emitter(value: &str) -> Option<Emitter> {
+    let mut words = value.split_whitespace();
+    let kind = words.next()?;
+    let count = if kind == "point" { 2 } else { 4 };
+    let mut coords = [0.0f32; 4];
+    for c in &mut coords[..count] {
+        *c = words.next()?.parse().ok()?;
+    }
+    let [a, b, c, d] = coords;
+    let region = match kind {
+        "point" => Region::Point { x: a, y: b },
+        "segment" => Region::Segment { x0: a, y0: b, x1: c, y1: d },
+        "box" => Region::Box { x: a, y: b, width: c, height: d },
+        _ => return None,
+    };
+    let mut numbers = [0.0f32; 4];
+    for n in &mut numbers {
+        *n = words.next()?.parse().ok()?;
+    }
+    let [rate, vx, vy, spread] = numbers;
+    let mut species = None;
+    let mut enabled = true;
+    let mut word = words.next();
+    if let Some(Ok(k)) = word.map(str::parse::<u8>) {
+        species = Some(k);
+        word = words.next();
+    }
+    if word == Some("off") {
+        enabled = false;
+        word = words.next();
+    }
+    if word.is_some() {
+        return None;
+    }
+    Some(Emitter { region, rate, vx, vy, spread, species, enabled })
+}
+
+fn parse_obstacle(value: &str) -> Option<Obstacle> {
+    let mut words = value.split_whitespace();
+    let kind = words.next()?;
+    let mut coords = [0.0f32; 4];
+    for c in &mut coords {
+        *c = words.next()?.parse().ok()?;
+    }
+    let [a, b, c, d] = coords;
+    let shape = match kind {
+        "segment" => Shape::Segment { x0: a, y0: b, x1: c, y1: d },
+        "box" => Shape::Box { x: a, y: b, width: c, height: d },
+        _ => return None,
+    };
+    let response = match words.next()? {
+        "land" => Response::Land,
+        "slide" => Response::Slide,
+        "deflect" => Response::Deflect,
+        _ => return None,
+    };
+    let output = match words.next() {
+        Some(word) => Some(word.parse().ok()?),
+        None => None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some(Obstacle { shape, response, output })
+}
+
+fn parse_zone(value: &str) -> Option<GroundZone> {
+    let mut words = value.split_whitespace();
+    let x0 = words.next()?.parse().ok()?;
+    let x1 = words.next()?.parse().ok()?;
+    let note = match words.next() {
+        Some(word) => Some(word.parse::<u8>().ok().filter(|&note| note < 128)?),
+        None => None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some(GroundZone { x0, x1, note })
+}
+
+fn parse_force_point(value: &str) -> Option<ForcePoint> {
+    let mut words = value.split_whitespace();
+    let polarity = match words.next()? {
+        "attract" => Polarity::Attract,
+        "repel" => Polarity::Repel,
+        _ => return None,
+    };
+    let mut numbers = [0.0f32; 4];
+    for n in &mut numbers {
+        *n = words.next()?.parse().ok()?;
+    }
+    if words.next().is_some() {
+        return None;
+    }
+    let [x, y, strength, falloff] = numbers;
+    Some(ForcePoint { x, y, strength, falloff, polarity })
+}
+
+fn parse_u32(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+// COMPAT: Keys missing from `text` keep their value from `base`,
+// `species`, `emitter`, `ground_zone`, `obstacle` and `force` lines replace the whole list
+pub fn parse_preset(text: &str, base: Settings) -> Result<Settings, PresetError> {
+    let mut s = base;
+    let mut species: Option<SpeciesTable> = None;
+    let mut emitters: Option<Emitters> = None;
+    let mut emitter_lines = [0usize; MAX_EMITTERS];
+    let mut zones: Option<GroundZones> = None;
+    let mut obstacles: Option<Obstacles> = None;
+    let mut force_points: Option<ForcePoints> = None;
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |kind| PresetError { line: index + 1, kind };
+        let (key, value) = line.split_once('=').ok_or(error(PresetErrorKind::MissingEquals))?;
+        let value = value.trim();
+        let invalid = || error(PresetErrorKind::InvalidValue);
+        let f = || value.parse::<f32>().map_err(|_| invalid());
+        // Accelerations that bring particles back down
+        let positive = || value.parse::<f32>().ok().filter(|&v| v > 0.0).ok_or(invalid());
+        let i = || value.parse::<i32>().map_err(|_| invalid());
+        let u = || value.parse::<usize>().map_err(|_| invalid());
+        match key.trim() {
+            "max_particles" => s.max_particles = u()?,
+            "max_dust" => s.max_dust = u()?,
+            "max_particles_array" => s.max_particles_array = u()?,
+            "max_dust_array" => s.max_dust_array = u()?,
+            "gravity" => s.gravity = f()?,
+            "gravity_x" => s.gravity_x = f()?,
+            "gravity_y" => s.gravity_y = f()?,
+            // Shorthand for the direction, radians from straight down towards +x
+            "gravity_angle" => gravity::set_angle(&mut s, f()?),
+            "global_fall_speed" => s.global_fall_speed = f()?,
+            "wind" => s.wind = f()?,
+            "motion" => {
+                s.motion = match value {
+                    "constant" => Motion::Constant,
+                    "physical" => Motion::Physical,
+                    _ => return Err(invalid()),
+                }
+            }
+            "fall_acceleration" => s.fall_acceleration = positive()?,
+            "terminal_velocity_scale" => s.terminal_velocity_scale = f()?,
+            "wind_field_strength" => s.wind_field_strength = f()?,
+            "wind_direction" => s.wind_direction = f()?,
+            "wind_turbulence_scale" => s.wind_turbulence_scale = f()?,
+            "wind_drift" => s.wind_drift = f()?,
+            "wind_gust" => s.wind_gust = f()?,
+            "collision_cooldown_time" => s.collision_cooldown_time = f()?,
+            "trigger_duration" => s.trigger_duration = f()?,
+            "verbose_duration" => s.verbose_duration = f()?,
+            "screen_width" => s.screen_width = i()?,
+            "screen_height" => s.screen_height = i()?,
+            "ground_level" => s.ground_level = i()?,
+            "particle_spawn_rate" => s.particle_spawn_rate = f()?,
+            // COMPAT: Older presets give a chance per 60 Hz frame
+            "particle_spawn_chance" => {
+                s.particle_spawn_rate = -libm::logf(1.0 - f()?.clamp(0.0, 0.999)) / DEFAULT_FIXED_STEP
+            }
+            "particle_min_size" => s.particle_min_size = f()?,
+            "particle_max_size" => s.particle_max_size = f()?,
+            "particle_sway_speed_min" => s.particle_sway_speed_min = f()?,
+            "particle_sway_speed_max" => s.particle_sway_speed_max = f()?,
+            "dust_dx_factor" => s.dust_dx_factor = f()?,
+            "dust_dy_max" => s.dust_dy_max = f()?,
+            "dust_life_min" => s.dust_life_min = f()?,
+            "dust_life_max" => s.dust_life_max = f()?,
+            "dust_brightness_max" => s.dust_brightness_max = value.parse().map_err(|_| invalid())?,
+            "dust_wake_strength" => s.dust_wake_strength = f()?,
+            "dust_wake_radius" => s.dust_wake_radius = f()?,
+            "dust_splash_count" => s.dust_splash_count = value.parse().map_err(|_| invalid())?,
+            "dust_splash_speed" => s.dust_splash_speed = f()?,
+            "collision_output_range" => s.collision_output_range = f()?,
+            "impact_speed_range" => s.impact_speed_range = f()?,
+            "collision_mode" => {
+                s.collision_mode = match value {
+                    "box" => CollisionMode::Box,
+                    "circle" => CollisionMode::Circle,
+                    _ => return Err(invalid()),
+                }
+            }
+            "collision_restitution" => s.collision_restitution = f()?,
+            "impulse_damping" => s.impulse_damping = f()?,
+            "fragment_count" => s.fragment_count = value.parse().map_err(|_| invalid())?,
+            "fragment_min_radius" => s.fragment_min_radius = f()?,
+            "fragment_scatter" => s.fragment_scatter = f()?,
+            "collision_merge" => s.collision_merge = value.parse().map_err(|_| invalid())?,
+            "merge_type_rule" => {
+                s.merge_type_rule = match value {
+                    "average" => MergeTypeRule::Average,
+                    "max" => MergeTypeRule::Max,
+                    "summod" => MergeTypeRule::SumMod,
+                    _ => return Err(invalid()),
+                }
+            }
+            "ground_mode" => {
+                s.ground_mode = match value {
+                    "retire" => GroundMode::Retire,
+                    "bounce" => GroundMode::Bounce,
+                    _ => return Err(invalid()),
+                }
+            }
+            "ground_restitution" => s.ground_restitution = f()?,
+            "bounce_gravity" => s.bounce_gravity = positive()?,
+            "bounce_min_height" => s.bounce_min_height = f()?,
+            "boundary_mode" => {
+                s.boundary_mode = match value {
+                    "clamp" => BoundaryMode::Clamp,
+                    "wrap" => BoundaryMode::Wrap,
+                    "bounce" => BoundaryMode::Bounce,
+                    "absorb" => BoundaryMode::Absorb,
+                    _ => return Err(invalid()),
+                }
+            }
+            "species" => {
+                let kind = parse_species(value).ok_or(invalid())?;
+                species.get_or_insert_with(SpeciesTable::new).push(kind).map_err(|_| invalid())?;
+            }
+            "emitter" => {
+                let emitter = parse_emitter(value).ok_or(invalid())?;
+                let list = emitters.get_or_insert_with(Emitters::new);
+                list.push(emitter).map_err(|_| invalid())?;
+                emitter_lines[list.len() - 1] = index + 1;
+            }
+            "ground_zone" => {
+                let zone = parse_zone(value).ok_or(invalid())?;
+                zones.get_or_insert_with(GroundZones::new).push(zone).map_err(|_| invalid())?;
+            }
+            "obstacle" => {
+                let o = parse_obstacle(value).ok_or(invalid())?;
+                obstacles.get_or_insert_with(Obstacles::new).push(o).map_err(|_| invalid())?;
+            }
+            "force" => {
+                let point = parse_force_point(value).ok_or(invalid())?;
+                force_points.get_or_insert_with(ForcePoints::new).push(point).map_err(|_| invalid())?;
+            }
+            "rng_seed" => s.rng_seed = parse_u32(value).ok_or(invalid())?,
+            _ => return Err(error(PresetErrorKind::UnknownKey)),
+        }
+    }
+    if let Some(species) = species {
+        s.species = species;
+    }
+    if let Some(emitters) = emitters {
+        // Species lines may come after the emitters using them
+        for (e, &line) in emitters.as_slice().iter().zip(&emitter_lines) {
+            if e.species.is_some_and(|k| k as usize >= s.species.len()) {
+                return Err(PresetError { line, kind: PresetErrorKind::InvalidValue });
+            }
+        }
+        s.emitters = emitters;
+    }
+    if let Some(zones) = zones {
+        s.ground_zones = zones;
+    }
+    if let Some(obstacles) = obstacles {
+        s.obstacles = obstacles;
+    }
+    if let Some(force_points) = force_points {
+        s.force_points = force_points;
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::String;
+
+    #[test]
+    fn test_round_trip() {
+        let mut settings = Settings {
+            max_particles: 9,
+            gravity: 2.5,
+            gravity_x: -0.5,
+            gravity_y: 0.25,
+            wind: 0.7,
+            ground_level: 120,
+            particle_spawn_rate: 25.0,
+            dust_brightness_max: 7,
+            collision_mode: CollisionMode::Circle,
+            ground_mode: GroundMode::Bounce,
+            boundary_mode: BoundaryMode::Wrap,
+            motion: Motion::Physical,
+            collision_merge: true,
+            merge_type_rule: MergeTypeRule::Max,
+            rng_seed: 0xDEADBEEF,
+            ..Settings::default()
+        };
+        let _ = settings.obstacles.push(Obstacle {
+            shape: Shape::Segment { x0: 40.0, y0: 120.5, x1: 100.0, y1: 110.0 },
+            response: Response::Slide,
+            output: None,
+        });
+        let _ = settings.obstacles.push(Obstacle {
+            shape: Shape::Box { x: 200.0, y: 90.0, width: 30.0, height: 8.0 },
+            response: Response::Land,
+            output: Some(40_000),
+        });
+        let _ = settings.species.push(Species {
+            weight: 0.5,
+            min_size: 8.0,
+            max_size: 10.0,
+            min_type: 1,
+            max_type: 2,
+            fall_speed: 0.4,
+            wind_sensitivity: 0.25,
+            output_map: OutputMap::Size,
+            channel: Some(3),
+        });
+        let _ = settings.species.push(Species { weight: 2.0, output_map: OutputMap::Position, ..Species::default() });
+        let _ = settings.emitters.push(Emitter {
+            region: Region::Point { x: 160.0, y: 10.5 },
+            rate: 4.0,
+            vx: -3.0,
+            vy: 12.0,
+            spread: 0.3,
+            species: Some(1),
+            enabled: false,
+        });
+        let _ = settings.emitters.push(Emitter {
+            region: Region::Box { x: 10.0, y: 0.0, width: 50.0, height: 20.0 },
+            rate: 2.0,
+            ..Emitter::default()
+        });
+        let _ = settings.ground_zones.push(GroundZone { x0: 0.0, x1: 106.5, note: Some(36) });
+        let _ = settings.ground_zones.push(GroundZone { x0: 106.5, x1: 213.0, note: None });
+        let _ = settings.force_points.push(ForcePoint {
+            x: 160.0,
+            y: 75.5,
+            strength: 30.0,
+            falloff: 40.0,
+            polarity: Polarity::Repel,
+        });
+        let mut text: String<2048> = String::new();
+        write_preset(&settings, &mut text).unwrap();
+        assert_eq!(parse_preset(&text, Settings::default()), Ok(settings));
+    }
+
+    #[test]
+    fn test_partial_preset_and_errors() {
+        let parsed = parse_preset("# slow\n\ngravity = 0.5  # heavy\nwind=0.3\n", Settings::default()).unwrap();
+        assert_eq!(parsed.gravity, 0.5);
+        assert_eq!(parsed.wind, 0.3);
+        assert_eq!(parsed.max_particles, Settings::default().max_particles);
+
+        let err = |text| parse_preset(text, Settings::default()).unwrap_err();
+        assert_eq!(err("gravity 1"), PresetError { line: 1, kind: PresetErrorKind::MissingEquals });
+        assert_eq!(err("\nsnow = 1"), PresetError { line: 2, kind: PresetErrorKind::UnknownKey });
+        assert_eq!(err("wind = fast"), PresetError { line: 1, kind: PresetErrorKind::InvalidValue });
+        assert_eq!(err("bounce_gravity = 0"), PresetError { line: 1, kind: PresetErrorKind::InvalidValue });
+        assert_eq!(err("fall_acceleration = -30"), PresetError { line: 1, kind: PresetErrorKind::InvalidValue });
+        // Ranges run from their lower end
+        assert_eq!(err("species = 1 10 3 1 7 1 1 size"), PresetError { line: 1, kind: PresetErrorKind::InvalidValue });
+        assert_eq!(err("species = 1 3 10 6 2 1 1 type"), PresetError { line: 1, kind: PresetErrorKind::InvalidValue });
+        // Emitters name one of the species
+        let preset = "emitter = point 10 10 5 0 0 0 1\nspecies = 1 3 10 1 7 1 1 blend\nspecies = 1 2 3 1 7 1 1 size\n";
+        assert!(parse_preset(preset, Settings::default()).is_ok());
+        let preset = "emitter = point 10 10 5 0 0 0 0\nspecies = 1 3 10 1 7 1 1 blend\nemitter = point 10 10 5 0 0 0 1\n";
+        assert_eq!(err(preset), PresetError { line: 3, kind: PresetErrorKind::InvalidValue });
+        assert_eq!(err("emitter = point 10 10 5 0 0 0 0"), PresetError { line: 1, kind: PresetErrorKind::InvalidValue });
+    }
+}
@@ -0,0 +1,210 @@
+//! quantizer - Optional scale quantizer for particle outputs
+//! Maps particle types or u16 outputs onto musical scales, no_std compatible
+
+// CHANGE: Restores the pitch/scale mapping of the Lua original
+// REASON: Notes must stay in key without re-implementing this on the host
+use heapless::Vec;
+
+// Maximum number of degrees in a scale (one per semitone)
+pub const MAX_SCALE_DEGREES: usize = 12;
+
+// Built-in modes, as semitone offsets from the root
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Major,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    NaturalMinor,
+    Locrian,
+    HarmonicMinor,
+    MajorPentatonic,
+    MinorPentatonic,
+    Chromatic,
+}
+
+impl Mode {
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            Mode::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Mode::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            Mode::Phrygian => &[0, 1, 3, 5, 7, 8, 10],
+            Mode::Lydian => &[0, 2, 4, 6, 7, 9, 11],
+            Mode::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            Mode::NaturalMinor => &[0, 2, 3, 5, 7, 8, 10],
+            Mode::Locrian => &[0, 1, 3, 5, 6, 8, 10],
+            Mode::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            Mode::MajorPentatonic => &[0, 2, 4, 7, 9],
+            Mode::MinorPentatonic => &[0, 3, 5, 7, 10],
+            Mode::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+}
+
+// Scale as an ascending interval table within one octave
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scale {
+    intervals: Vec<u8, MAX_SCALE_DEGREES>,
+}
+
+impl Scale {
+    pub fn from_mode(mode: Mode) -> Self {
+        let mut intervals = Vec::new();
+        let _ = intervals.extend_from_slice(mode.intervals());
+        Self { intervals }
+    }
+
+    // User-defined table: must start at 0, be strictly ascending and stay below 12
+    pub fn custom(intervals: &[u8]) -> Option<Self> {
+        if intervals.first() != Some(&0) || intervals.len() > MAX_SCALE_DEGREES {
+            return None;
+        }
+        if intervals.windows(2).any(|w| w[0] >= w[1]) || intervals.iter().any(|&i| i >= 12) {
+            return None;
+        }
+        let mut table = Vec::new();
+        let _ = table.extend_from_slice(intervals);
+        Some(Self { intervals: table })
+    }
+
+    pub fn degrees(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn intervals(&self) -> &[u8] {
+        &self.intervals
+    }
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self::from_mode(Mode::Major)
+    }
+}
+
+// 1V/oct DAC calibration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DacCalibration {
+    // DAC codes per volt (6553.5 for a 16-bit DAC spanning 10 V)
+    pub codes_per_volt: f32,
+    // MIDI note produced at 0 V
+    pub zero_volt_note: u8,
+}
+
+impl Default for DacCalibration {
+    fn default() -> Self {
+        Self {
+            codes_per_volt: u16::MAX as f32 / 10.0,
+            zero_volt_note: 24,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Quantizer {
+    // MIDI note of degree 0
+    pub root: u8,
+    pub scale: Scale,
+    // Octaves spanned when mapping u16 outputs
+    pub octaves: u8,
+    pub dac: DacCalibration,
+}
+
+impl Default for Quantizer {
+    fn default() -> Self {
+        Self {
+            root: 48,
+            scale: Scale::default(),
+            octaves: 3,
+            dac: DacCalibration::default(),
+        }
+    }
+}
+
+impl Quantizer {
+    pub fn new(root: u8, scale: Scale) -> Self {
+        Self { root, scale, ..Self::default() }
+    }
+
+    // Scale degree to MIDI note, degrees past the table wrap into higher octaves
+    pub fn degree_to_note(&self, degree: i32) -> u8 {
+        let n = self.scale.degrees() as i32;
+        // Past 11 octaves every note clamps anyway, and octave * 12 cannot overflow
+        let octave = degree.div_euclid(n).clamp(-11, 11);
+        let step = self.scale.intervals[degree.rem_euclid(n) as usize] as i32;
+        (self.root as i32 + octave * 12 + step).clamp(0, 127) as u8
+    }
+
+    // COMPAT: particle_type 1-7 maps to the seven degrees of the original
+    pub fn type_to_note(&self, particle_type: u8) -> u8 {
+        self.degree_to_note(particle_type as i32 - 1)
+    }
+
+    // Normalized output spread over `octaves` octaves of the scale
+    pub fn output_to_note(&self, output: u16) -> u8 {
+        let steps = self.scale.degrees() as u32 * self.octaves.max(1) as u32;
+        let degree = (output as u32 * steps) >> 16;
+        self.degree_to_note(degree as i32)
+    }
+
+    pub fn note_to_dac(&self, note: u8) -> u16 {
+        let volts = (note as f32 - self.dac.zero_volt_note as f32) / 12.0;
+        (volts * self.dac.codes_per_volt + 0.5).clamp(0.0, u16::MAX as f32) as u16
+    }
+
+    pub fn type_to_dac(&self, particle_type: u8) -> u16 {
+        self.note_to_dac(self.type_to_note(particle_type))
+    }
+
+    pub fn output_to_dac(&self, output: u16) -> u16 {
+        self.note_to_dac(self.output_to_note(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_maps_to_major_degrees() {
+        let q = Quantizer::new(60, Scale::from_mode(Mode::Major));
+        assert_eq!(q.type_to_note(1), 60);
+        assert_eq!(q.type_to_note(3), 64);
+        assert_eq!(q.type_to_note(7), 71);
+        assert_eq!(q.degree_to_note(7), 72);
+        assert_eq!(q.degree_to_note(-1), 59);
+        assert_eq!(q.degree_to_note(i32::MAX), 127);
+        assert_eq!(q.degree_to_note(i32::MIN), 0);
+    }
+
+    #[test]
+    fn test_output_stays_in_scale() {
+        let q = Quantizer::new(57, Scale::from_mode(Mode::MinorPentatonic));
+        for output in (0..=u16::MAX).step_by(97) {
+            let offset = (q.output_to_note(output) as i32 - 57).rem_euclid(12) as u8;
+            assert!(q.scale.intervals().contains(&offset));
+        }
+        assert_eq!(q.output_to_note(0), 57);
+        assert!(q.output_to_note(u16::MAX) < 57 + 12 * 3);
+    }
+
+    #[test]
+    fn test_custom_scale_validation() {
+        assert!(Scale::custom(&[0, 3, 7]).is_some());
+        assert!(Scale::custom(&[]).is_none());
+        assert!(Scale::custom(&[2, 4]).is_none());
+        assert!(Scale::custom(&[0, 7, 3]).is_none());
+        assert!(Scale::custom(&[0, 12]).is_none());
+    }
+
+    #[test]
+    fn test_one_volt_per_octave() {
+        let q = Quantizer::default();
+        let c2 = q.note_to_dac(36);
+        let c3 = q.note_to_dac(48);
+        assert_eq!(q.note_to_dac(24), 0);
+        assert!((c3 as f32 - c2 as f32 - q.dac.codes_per_volt).abs() <= 1.0);
+        assert_eq!(q.note_to_dac(0), 0);
+    }
+}
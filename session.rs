@@ -0,0 +1,760 @@
+//! session - Deterministic record and replay of particle sessions
+//! Compact binary log of settings, dt values and settings changes, no_std compatible
+
+use crate::emitter::{Emitter, Emitters, Region, MAX_EMITTERS};
+use crate::force::{ForcePoint, ForcePoints, Polarity, MAX_FORCE_POINTS};
+use crate::obstacle::{Obstacle, Obstacles, Response, Shape, MAX_OBSTACLES};
+use crate::species::{OutputMap, Species, SpeciesTable, MAX_SPECIES};
+use crate::zone::{GroundZone, GroundZones, MAX_GROUND_ZONES};
+use crate::{BoundaryMode, CollisionMode, GroundMode, MergeTypeRule, Motion, ParticlesSystem, Settings};
+
+// Log layout, all values little endian:
+//   "PSES" version:u8 settings
+//   records: STEP dt:f32 | RUN count:u32 dt:f32 | SETTINGS time:f32 settings
+//   END
+// settings: length:u16 followed by every `Settings` field in declaration order
+// species, emitters, zones, obstacles, force points: count:u8 then every slot, unused ones zeroed
+const MAGIC: &[u8; 4] = b"PSES";
+const VERSION: u8 = 2;
+
+const RECORD_STEP: u8 = 0x01;
+const RECORD_RUN: u8 = 0x02;
+const RECORD_SETTINGS: u8 = 0x03;
+const RECORD_END: u8 = 0xFF;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    BadHeader,
+    // Written by a build with a different `Settings` layout
+    SettingsMismatch,
+    Truncated,
+    UnknownRecord(u8),
+}
+
+// Encoded size of one species slot: weight, sizes, types, scales, output map, channel
+const SPECIES_LEN: usize = 4 * 3 + 1 + 1 + 4 * 2 + 1 + 1 + 1;
+
+// Encoded size of one emitter slot: region, 4 coordinates, rate, velocity, spread, species, enabled
+const EMITTER_LEN: usize = 1 + 4 * 4 + 4 + 4 * 2 + 4 + 1 + 1 + 1;
+
+// Encoded size of one ground zone slot: span, note
+const ZONE_LEN: usize = 4 * 2 + 1 + 1;
+
+// Encoded size of one obstacle slot: shape, 4 coordinates, response, output
+const OBSTACLE_LEN: usize = 1 + 4 * 4 + 1 + 1 + 2;
+
+// Encoded size of one force point slot: position, strength, falloff, polarity
+const FORCE_POINT_LEN: usize = 4 * 4 + 1;
+
+// Encoded size of the plain fields: 44 four-byte numbers and 9 one-byte enums, counts and flags
+const FIELDS_LEN: usize = 4 * 44 + 9;
+
+// Encoded size of each list: count, then every slot
+const SPECIES_TABLE_LEN: usize = 1 + MAX_SPECIES * SPECIES_LEN;
+const EMITTERS_LEN: usize = 1 + MAX_EMITTERS * EMITTER_LEN;
+const GROUND_ZONES_LEN: usize = 1 + MAX_GROUND_ZONES * ZONE_LEN;
+const OBSTACLES_LEN: usize = 1 + MAX_OBSTACLES * OBSTACLE_LEN;
+const FORCE_POINTS_LEN: usize = 1 + MAX_FORCE_POINTS * FORCE_POINT_LEN;
+
+// Encoded size of the fields written by `write_settings`
+const SETTINGS_LEN: usize =
+    FIELDS_LEN + SPECIES_TABLE_LEN + EMITTERS_LEN + GROUND_ZONES_LEN + OBSTACLES_LEN + FORCE_POINTS_LEN;
+
+// The block length is written as a u16
+const _: () = assert!(SETTINGS_LEN <= u16::MAX as usize);
+
+// CHANGE: Settings encoded field by field
+// REASON: Stable byte layout independent of struct padding
+fn write_settings(s: &Settings, out: &mut impl FnMut(&[u8])) {
+    let mut block = [0u8; SETTINGS_LEN];
+    let mut w = Writer { buf: &mut block, len: 0 };
+    w.u32(s.max_particles as u32);
+    w.u32(s.max_dust as u32);
+    w.u32(s.max_particles_array as u32);
+    w.u32(s.max_dust_array as u32);
+    w.f32(s.gravity);
+    w.f32(s.gravity_x);
+    w.f32(s.gravity_y);
+    w.f32(s.global_fall_speed);
+    w.f32(s.wind);
+    w.u8(s.motion as u8);
+    w.f32(s.fall_acceleration);
+    w.f32(s.terminal_velocity_scale);
+    w.f32(s.wind_field_strength);
+    w.f32(s.wind_direction);
+    w.f32(s.wind_turbulence_scale);
+    w.f32(s.wind_drift);
+    w.f32(s.wind_gust);
+    w.f32(s.collision_cooldown_time);
+    w.f32(s.trigger_duration);
+    w.f32(s.verbose_duration);
+    w.i32(s.screen_width);
+    w.i32(s.screen_height);
+    w.i32(s.ground_level);
+    w.f32(s.particle_spawn_rate);
+    w.f32(s.particle_min_size);
+    w.f32(s.particle_max_size);
+    w.f32(s.particle_sway_speed_min);
+    w.f32(s.particle_sway_speed_max);
+    w.u8(s.species.len() as u8);
+    for k in 0..MAX_SPECIES {
+        match s.species.as_slice().get(k) {
+            Some(species) => write_species(species, &mut w),
+            None => w.bytes(&[0; SPECIES_LEN]),
+        }
+    }
+    w.u8(s.emitters.len() as u8);
+    for k in 0..MAX_EMITTERS {
+        match s.emitters.as_slice().get(k) {
+            Some(emitter) => write_emitter(emitter, &mut w),
+            None => w.bytes(&[0; EMITTER_LEN]),
+        }
+    }
+    w.f32(s.dust_dx_factor);
+    w.f32(s.dust_dy_max);
+    w.f32(s.dust_life_min);
+    w.f32(s.dust_life_max);
+    w.u8(s.dust_brightness_max);
+    w.f32(s.dust_wake_strength);
+    w.f32(s.dust_wake_radius);
+    w.u8(s.dust_splash_count);
+    w.f32(s.dust_splash_speed);
+    w.f32(s.collision_output_range);
+    w.f32(s.impact_speed_range);
+    w.u8(s.collision_mode as u8);
+    w.f32(s.collision_restitution);
+    w.f32(s.impulse_damping);
+    w.u8(s.fragment_count);
+    w.f32(s.fragment_min_radius);
+    w.f32(s.fragment_scatter);
+    w.u8(s.collision_merge as u8);
+    w.u8(s.merge_type_rule as u8);
+    w.u8(s.ground_mode as u8);
+    w.f32(s.ground_restitution);
+    w.f32(s.bounce_gravity);
+    w.f32(s.bounce_min_height);
+    w.u8(s.boundary_mode as u8);
+    w.u8(s.ground_zones.len() as u8);
+    for k in 0..MAX_GROUND_ZONES {
+        match s.ground_zones.as_slice().get(k) {
+            Some(zone) => write_zone(zone, &mut w),
+            None => w.bytes(&[0; ZONE_LEN]),
+        }
+    }
+    w.u8(s.obstacles.len() as u8);
+    for k in 0..MAX_OBSTACLES {
+        match s.obstacles.as_slice().get(k) {
+            Some(o) => write_obstacle(o, &mut w),
+            None => w.bytes(&[0; OBSTACLE_LEN]),
+        }
+    }
+    w.u8(s.force_points.len() as u8);
+    for k in 0..MAX_FORCE_POINTS {
+        match s.force_points.as_slice().get(k) {
+            Some(point) => write_force_point(point, &mut w),
+            None => w.bytes(&[0; FORCE_POINT_LEN]),
+        }
+    }
+    w.u32(s.rng_seed);
+    // A short block would replay as zero padding, test_settings_layout_covers_every_field
+    // checks the size in every build
+    debug_assert_eq!(w.len, SETTINGS_LEN);
+
+    out(&(SETTINGS_LEN as u16).to_le_bytes());
+    out(&block);
+}
+
+fn read_settings(r: &mut Reader) -> Result<Settings, ReplayError> {
+    if r.u16()? as usize != SETTINGS_LEN {
+        return Err(ReplayError::SettingsMismatch);
+    }
+    Ok(Settings {
+        max_particles: r.u32()? as usize,
+        max_dust: r.u32()? as usize,
+        max_particles_array: r.u32()? as usize,
+        max_dust_array: r.u32()? as usize,
+        gravity: r.f32()?,
+        gravity_x: r.f32()?,
+        gravity_y: r.f32()?,
+        global_fall_speed: r.f32()?,
+        wind: r.f32()?,
+        motion: match r.u8()? {
+            0 => Motion::Constant,
+            1 => Motion::Physical,
+            _ => return Err(ReplayError::SettingsMismatch),
+        },
+        fall_acceleration: r.f32()?,
+        terminal_velocity_scale: r.f32()?,
+        wind_field_strength: r.f32()?,
+        wind_direction: r.f32()?,
+        wind_turbulence_scale: r.f32()?,
+        wind_drift: r.f32()?,
+        wind_gust: r.f32()?,
+        collision_cooldown_time: r.f32()?,
+        trigger_duration: r.f32()?,
+        verbose_duration: r.f32()?,
+        screen_width: r.i32()?,
+        screen_height: r.i32()?,
+        ground_level: r.i32()?,
+        particle_spawn_rate: r.f32()?,
+        particle_min_size: r.f32()?,
+        particle_max_size: r.f32()?,
+        particle_sway_speed_min: r.f32()?,
+        particle_sway_speed_max: r.f32()?,
+        species: {
+            let count = r.u8()? as usize;
+            let mut table = SpeciesTable::new();
+            for k in 0..MAX_SPECIES {
+                let species = read_species(r)?;
+                if k < count {
+                    let _ = table.push(species);
+                }
+            }
+            table
+        },
+        emitters: {
+            let count = r.u8()? as usize;
+            let mut emitters = Emitters::new();
+            for k in 0..MAX_EMITTERS {
+                let emitter = read_emitter(r)?;
+                if k < count {
+                    let _ = emitters.push(emitter);
+                }
+            }
+            emitters
+        },
+        dust_dx_factor: r.f32()?,
+        dust_dy_max: r.f32()?,
+        dust_life_min: r.f32()?,
+        dust_life_max: r.f32()?,
+        dust_brightness_max: r.u8()?,
+        dust_wake_strength: r.f32()?,
+        dust_wake_radius: r.f32()?,
+        dust_splash_count: r.u8()?,
+        dust_splash_speed: r.f32()?,
+        collision_output_range: r.f32()?,
+        impact_speed_range: r.f32()?,
+        collision_mode: match r.u8()? {
+            0 => CollisionMode::Box,
+            1 => CollisionMode::Circle,
+            _ => return Err(ReplayError::SettingsMismatch),
+        },
+        collision_restitution: r.f32()?,
+        impulse_damping: r.f32()?,
+        fragment_count: r.u8()?,
+        fragment_min_radius: r.f32()?,
+        fragment_scatter: r.f32()?,
+        collision_merge: r.u8()? != 0,
+        merge_type_rule: match r.u8()? {
+            0 => MergeTypeRule::Average,
+            1 => MergeTypeRule::Max,
+            2 => MergeTypeRule::SumMod,
+            _ => return Err(ReplayError::SettingsMismatch),
+        },
+        ground_mode: match r.u8()? {
+            0 => GroundMode::Retire,
+            1 => GroundMode::Bounce,
+            _ => return Err(ReplayError::SettingsMismatch),
+        },
+        ground_restitution: r.f32()?,
+        bounce_gravity: r.f32()?,
+        bounce_min_height: r.f32()?,
+        boundary_mode: match r.u8()? {
+            0 => BoundaryMode::Clamp,
+            1 => BoundaryMode::Wrap,
+            2 => BoundaryMode::Bounce,
+            3 => BoundaryMode::Absorb,
+            _ => return Err(ReplayError::SettingsMismatch),
+        },
+        ground_zones: {
+            let count = r.u8()? as usize;
+            let mut zones = GroundZones::new();
+            for k in 0..MAX_GROUND_ZONES {
+                let zone = read_zone(r)?;
+                if k < count {
+                    let _ = zones.push(zone);
+                }
+            }
+            zones
+        },
+        obstacles: {
+            let count = r.u8()? as usize;
+            let mut obstacles = Obstacles::new();
+            for k in 0..MAX_OBSTACLES {
+                let o = read_obstacle(r)?;
+                if k < count {
+                    let _ = obstacles.push(o);
+                }
+            }
+            obstacles
+        },
+        force_points: {
+            let count = r.u8()? as usize;
+            let mut points = ForcePoints::new();
+            for k in 0..MAX_FORCE_POINTS {
+                let point = read_force_point(r)?;
+                if k < count {
+                    let _ = points.push(point);
+                }
+            }
+            points
+        },
+        rng_seed: r.u32()?,
+    })
+}
+
+fn write_species(s: &Species, w: &mut Writer) {
+    w.f32(s.weight);
+    w.f32(s.min_size);
+    w.f32(s.max_size);
+    w.u8(s.min_type);
+    w.u8(s.max_type);
+    w.f32(s.fall_speed);
+    w.f32(s.wind_sensitivity);
+    w.u8(s.output_map as u8);
+    w.u8(s.channel.is_some() as u8);
+    w.u8(s.channel.unwrap_or(0));
+}
+
+fn read_species(r: &mut Reader) -> Result<Species, ReplayError> {
+    let [weight, min_size, max_size] = [r.f32()?, r.f32()?, r.f32()?];
+    let (min_type, max_type) = (r.u8()?, r.u8()?);
+    let (fall_speed, wind_sensitivity) = (r.f32()?, r.f32()?);
+    let output_map = match r.u8()? {
+        0 => OutputMap::Blend,
+        1 => OutputMap::Position,
+        2 => OutputMap::Type,
+        3 => OutputMap::Size,
+        _ => return Err(ReplayError::SettingsMismatch),
+    };
+    let has_channel = r.u8()? != 0;
+    let channel = r.u8()?;
+    Ok(Species {
+        weight,
+        min_size,
+        max_size,
+        min_type,
+        max_type,
+        fall_speed,
+        wind_sensitivity,
+        output_map,
+        channel: has_channel.then_some(channel),
+    })
+}
+
+fn write_emitter(e: &Emitter, w: &mut Writer) {
+    let (tag, coords) = match e.region {
+        Region::Point { x, y } => (0, [x, y, 0.0, 0.0]),
+        Region::Segment { x0, y0, x1, y1 } => (1, [x0, y0, x1, y1]),
+        Region::Box { x, y, width, height } => (2, [x, y, width, height]),
+    };
+    w.u8(tag);
+    for c in coords {
+        w.f32(c);
+    }
+    w.f32(e.rate);
+    w.f32(e.vx);
+    w.f32(e.vy);
+    w.f32(e.spread);
+    w.u8(e.species.is_some() as u8);
+    w.u8(e.species.unwrap_or(0));
+    w.u8(e.enabled as u8);
+}
+
+fn read_emitter(r: &mut Reader) -> Result<Emitter, ReplayError> {
+    let tag = r.u8()?;
+    let [a, b, c, d] = [r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+    let region = match tag {
+        0 => Region::Point { x: a, y: b },
+        1 => Region::Segment { x0: a, y0: b, x1: c, y1: d },
+        2 => Region::Box { x: a, y: b, width: c, height: d },
+        _ => return Err(ReplayError::SettingsMismatch),
+    };
+    let [rate, vx, vy, spread] = [r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+    let has_species = r.u8()? != 0;
+    let species = r.u8()?;
+    let enabled = r.u8()? != 0;
+    Ok(Emitter { region, rate, vx, vy, spread, species: has_species.then_some(species), enabled })
+}
+
+fn write_zone(zone: &GroundZone, w: &mut Writer) {
+    w.f32(zone.x0);
+    w.f32(zone.x1);
+    w.u8(zone.note.is_some() as u8);
+    w.u8(zone.note.unwrap_or(0));
+}
+
+fn read_zone(r: &mut Reader) -> Result<GroundZone, ReplayError> {
+    let (x0, x1) = (r.f32()?, r.f32()?);
+    let has_note = r.u8()? != 0;
+    let note = r.u8()?;
+    Ok(GroundZone { x0, x1, note: has_note.then_some(note) })
+}
+
+fn write_obstacle(o: &Obstacle, w: &mut Writer) {
+    let (tag, coords) = match o.shape {
+        Shape::Segment { x0, y0, x1, y1 } => (0, [x0, y0, x1, y1]),
+        Shape::Box { x, y, width, height } => (1, [x, y, width, height]),
+    };
+    w.u8(tag);
+    for c in coords {
+        w.f32(c);
+    }
+    w.u8(o.response as u8);
+    w.u8(o.output.is_some() as u8);
+    w.u16(o.output.unwrap_or(0));
+}
+
+fn read_obstacle(r: &mut Reader) -> Result<Obstacle, ReplayError> {
+    let tag = r.u8()?;
+    let [a, b, c, d] = [r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+    let shape = match tag {
+        0 => Shape::Segment { x0: a, y0: b, x1: c, y1: d },
+        1 => Shape::Box { x: a, y: b, width: c, height: d },
+        _ => return Err(ReplayError::SettingsMismatch),
+    };
+    let response = match r.u8()? {
+        0 => Response::Land,
+        1 => Response::Slide,
+        2 => Response::Deflect,
+        _ => return Err(ReplayError::SettingsMismatch),
+    };
+    let has_output = r.u8()? != 0;
+    let output = r.u16()?;
+    Ok(Obstacle { shape, response, output: has_output.then_some(output) })
+}
+
+fn write_force_point(point: &ForcePoint, w: &mut Writer) {
+    w.f32(point.x);
+    w.f32(point.y);
+    w.f32(point.strength);
+    w.f32(point.falloff);
+    w.u8(point.polarity as u8);
+}
+
+fn read_force_point(r: &mut Reader) -> Result<ForcePoint, ReplayError> {
+    let [x, y, strength, falloff] = [r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+    let polarity = match r.u8()? {
+        0 => Polarity::Attract,
+        1 => Polarity::Repel,
+        _ => return Err(ReplayError::SettingsMismatch),
+    };
+    Ok(ForcePoint { x, y, strength, falloff, polarity })
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+    fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+    fn u16(&mut self, v: u16) {
+        self.bytes(&v.to_le_bytes());
+    }
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+    fn i32(&mut self, v: i32) {
+        self.bytes(&v.to_le_bytes());
+    }
+    fn f32(&mut self, v: f32) {
+        self.bytes(&v.to_le_bytes());
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        let bytes = self.data.get(self.pos..self.pos + N).ok_or(ReplayError::Truncated)?;
+        self.pos += N;
+        let mut out = [0u8; N];
+        out.copy_from_slice(bytes);
+        Ok(out)
+    }
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take::<1>()?[0])
+    }
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+    fn i32(&mut self) -> Result<i32, ReplayError> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+}
+
+// CHANGE: Records every input of a session as it is applied
+// REASON: The xorshift RNG makes the rest of the run reproducible
+pub struct Recorder<W: FnMut(&[u8])> {
+    out: W,
+    run_dt: f32,
+    run_count: u32,
+}
+
+impl<W: FnMut(&[u8])> Recorder<W> {
+    // `settings` must be the ones the system was created with
+    pub fn new(settings: &Settings, mut out: W) -> Self {
+        out(MAGIC);
+        out(&[VERSION]);
+        write_settings(settings, &mut out);
+        Self { out, run_dt: 0.0, run_count: 0 }
+    }
+
+    pub fn update<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
+        &mut self,
+        system: &mut ParticlesSystem<MAX_PARTICLES, MAX_DUST>,
+        dt: f32,
+    ) {
+        // PERF: Identical dt values, e.g. from `FixedStep`, collapse into one record
+        if self.run_count > 0 && dt.to_bits() != self.run_dt.to_bits() {
+            self.flush_run();
+        }
+        self.run_dt = dt;
+        self.run_count += 1;
+        system.update(dt);
+    }
+
+    pub fn update_settings<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
+        &mut self,
+        system: &mut ParticlesSystem<MAX_PARTICLES, MAX_DUST>,
+        settings: Settings,
+    ) {
+        self.flush_run();
+        (self.out)(&[RECORD_SETTINGS]);
+        (self.out)(&system.time.to_le_bytes());
+        write_settings(&settings, &mut self.out);
+        system.update_settings(settings);
+    }
+
+    // Terminate the log and hand back the sink
+    pub fn finish(mut self) -> W {
+        self.flush_run();
+        (self.out)(&[RECORD_END]);
+        self.out
+    }
+
+    fn flush_run(&mut self) {
+        match self.run_count {
+            0 => {}
+            1 => {
+                (self.out)(&[RECORD_STEP]);
+                (self.out)(&self.run_dt.to_le_bytes());
+            }
+            count => {
+                (self.out)(&[RECORD_RUN]);
+                (self.out)(&count.to_le_bytes());
+                (self.out)(&self.run_dt.to_le_bytes());
+            }
+        }
+        self.run_count = 0;
+    }
+}
+
+// Replays a recorded log one `update` at a time
+pub struct Replay<'a> {
+    reader: Reader<'a>,
+    settings: Settings,
+    run_dt: f32,
+    run_left: u32,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader { data, pos: 0 };
+        if &reader.take::<4>()? != MAGIC || reader.u8()? != VERSION {
+            return Err(ReplayError::BadHeader);
+        }
+        let settings = read_settings(&mut reader)?;
+        Ok(Self { reader, settings, run_dt: 0.0, run_left: 0 })
+    }
+
+    // Settings to create the system with
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    // Apply recorded settings changes, then one update; `Ok(false)` at the end
+    pub fn step<const MAX_PARTICLES: usize, const MAX_DUST: usize>(
+        &mut self,
+        system: &mut ParticlesSystem<MAX_PARTICLES, MAX_DUST>,
+    ) -> Result<bool, ReplayError> {
+        while self.run_left == 0 {
+            match self.reader.u8()? {
+                RECORD_STEP => {
+                    self.run_dt = self.reader.f32()?;
+                    self.run_left = 1;
+                }
+                RECORD_RUN => {
+                    self.run_left = self.reader.u32()?;
+                    self.run_dt = self.reader.f32()?;
+                }
+                RECORD_SETTINGS => {
+                    let _time = self.reader.f32()?;
+                    self.settings = read_settings(&mut self.reader)?;
+                    system.update_settings(self.settings);
+                }
+                RECORD_END => return Ok(false),
+                other => return Err(ReplayError::UnknownRecord(other)),
+            }
+        }
+        self.run_left -= 1;
+        system.update(self.run_dt);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParticleEvent;
+    use heapless::Vec;
+
+    #[test]
+    fn test_replay_reproduces_events() {
+        let settings = Settings { rng_seed: 0xBEEF, ..Settings::default() };
+        let mut log: Vec<u8, 8192> = Vec::new();
+        let mut recorded: Vec<ParticleEvent, 256> = Vec::new();
+
+        let mut system: ParticlesSystem<12, 50> = ParticlesSystem::new(settings);
+        let mut recorder = Recorder::new(&settings, |b: &[u8]| { let _ = log.extend_from_slice(b); });
+        for i in 0..2000 {
+            let dt = if i % 7 == 0 { 0.02 } else { 1.0 / 60.0 };
+            recorder.update(&mut system, dt);
+            if i == 900 {
+                let mut changed = Settings { gravity: 3.0, wind: 0.6, max_particles: 11, ..settings };
+                let _ = changed.obstacles.push(Obstacle {
+                    shape: Shape::Segment { x0: 60.0, y0: 100.0, x1: 200.0, y1: 110.0 },
+                    response: Response::Deflect,
+                    output: Some(1234),
+                });
+                let _ = changed.emitters.push(Emitter {
+                    region: Region::Segment { x0: 100.0, y0: 20.0, x1: 220.0, y1: 20.0 },
+                    rate: 5.0,
+                    vy: 10.0,
+                    spread: 0.5,
+                    ..Emitter::default()
+                });
+                recorder.update_settings(&mut system, changed);
+            }
+            for event in system.drain_events() {
+                let _ = recorded.push(event);
+            }
+        }
+        let _ = recorder.finish();
+
+        let mut replay = Replay::new(&log).unwrap();
+        let mut system: ParticlesSystem<12, 50> = ParticlesSystem::new(replay.settings());
+        let mut replayed: Vec<ParticleEvent, 256> = Vec::new();
+        while replay.step(&mut system).unwrap() {
+            for event in system.drain_events() {
+                let _ = replayed.push(event);
+            }
+        }
+        assert!(recorded.len() > 10);
+        assert_eq!(recorded, replayed);
+    }
+
+    #[test]
+    fn test_settings_layout_covers_every_field() {
+        // Every field spelled out and off its default, so a new one has to be added here
+        let mut settings = Settings {
+            max_particles: 7,
+            max_dust: 40,
+            max_particles_array: 13,
+            max_dust_array: 41,
+            gravity: 1.5,
+            gravity_x: 0.25,
+            gravity_y: 0.75,
+            global_fall_speed: 4.5,
+            wind: 0.2,
+            motion: Motion::Physical,
+            fall_acceleration: 25.0,
+            terminal_velocity_scale: 1.5,
+            wind_field_strength: 3.0,
+            wind_direction: 0.5,
+            wind_turbulence_scale: 60.0,
+            wind_drift: 15.0,
+            wind_gust: 0.25,
+            collision_cooldown_time: 2.0,
+            trigger_duration: 0.1,
+            verbose_duration: 2.0,
+            screen_width: 321,
+            screen_height: 171,
+            ground_level: 151,
+            particle_spawn_rate: 10.0,
+            particle_min_size: 2.0,
+            particle_max_size: 9.0,
+            particle_sway_speed_min: 0.2,
+            particle_sway_speed_max: 0.4,
+            species: SpeciesTable::new(),
+            emitters: Emitters::new(),
+            dust_dx_factor: 9.0,
+            dust_dy_max: 4.0,
+            dust_life_min: 2.0,
+            dust_life_max: 9.0,
+            dust_brightness_max: 4,
+            dust_wake_strength: 150.0,
+            dust_wake_radius: 10.0,
+            dust_splash_count: 3,
+            dust_splash_speed: 25.0,
+            collision_output_range: 8.0,
+            impact_speed_range: 40.0,
+            collision_mode: CollisionMode::Circle,
+            collision_restitution: 0.5,
+            impulse_damping: 1.5,
+            fragment_count: 2,
+            fragment_min_radius: 1.5,
+            fragment_scatter: 10.0,
+            collision_merge: true,
+            merge_type_rule: MergeTypeRule::SumMod,
+            ground_mode: GroundMode::Bounce,
+            ground_restitution: 0.6,
+            bounce_gravity: 15.0,
+            bounce_min_height: 0.1,
+            boundary_mode: BoundaryMode::Absorb,
+            ground_zones: GroundZones::new(),
+            obstacles: Obstacles::new(),
+            force_points: ForcePoints::new(),
+            rng_seed: 0xBEEF,
+        };
+        let _ = settings.species.push(Species { min_size: 4.0, channel: Some(2), ..Species::default() });
+        let _ = settings.emitters.push(Emitter { rate: 2.0, species: Some(0), ..Emitter::default() });
+        let _ = settings.ground_zones.push(GroundZone { x0: 0.0, x1: 100.0, note: Some(40) });
+        let _ = settings.obstacles.push(Obstacle {
+            shape: Shape::Box { x: 10.0, y: 20.0, width: 30.0, height: 40.0 },
+            response: Response::Slide,
+            output: Some(99),
+        });
+        let _ = settings.force_points.push(ForcePoint { x: 50.0, strength: 20.0, ..ForcePoint::default() });
+
+        let mut block: Vec<u8, 1024> = Vec::new();
+        write_settings(&settings, &mut |b: &[u8]| { let _ = block.extend_from_slice(b); });
+        assert_eq!(block.len(), 2 + SETTINGS_LEN);
+        let mut r = Reader { data: &block, pos: 0 };
+        assert_eq!(read_settings(&mut r), Ok(settings));
+        assert_eq!(r.pos, block.len());
+    }
+
+    #[test]
+    fn test_invalid_logs_are_rejected() {
+        assert_eq!(Replay::new(b"nope").err(), Some(ReplayError::BadHeader));
+        assert_eq!(Replay::new(b"PSES\x02\x05").err(), Some(ReplayError::Truncated));
+        assert_eq!(Replay::new(b"PSES\x02\x05\x00").err(), Some(ReplayError::SettingsMismatch));
+    }
+}
@@ -0,0 +1,107 @@
+//! species - Particle kinds with their own spawn and output parameters
+//! Size, type and speed ranges, output mapping and the weighted pick at spawn
+
+use crate::list::FixedList;
+
+// Upper bound on species, each one has its own output and MIDI channel
+pub const MAX_SPECIES: usize = 4;
+
+// What the output of a particle follows
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputMap {
+    // COMPAT: Original blend of position, type and size
+    Blend,
+    // Position across the floor only
+    Position,
+    // Type only
+    Type,
+    // Size within the species size range only
+    Size,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Species {
+    // Share of the spawns, relative to the other species
+    pub weight: f32,
+    pub min_size: f32,
+    pub max_size: f32,
+    // Types drawn from min_type..=max_type, within 1..=7
+    pub min_type: u8,
+    pub max_type: u8,
+    // Multipliers on the fall speed and wind sensitivity derived from the size
+    pub fall_speed: f32,
+    pub wind_sensitivity: f32,
+    pub output_map: OutputMap,
+    // MIDI channel of its ground hits (zero-based), `None` uses the ground channel
+    pub channel: Option<u8>,
+}
+
+// Unused slots of the list
+impl Default for Species {
+    fn default() -> Self {
+        Self {
+            weight: 1.0,
+            min_size: 3.0,
+            max_size: 10.0,
+            min_type: 1,
+            max_type: 7,
+            fall_speed: 1.0,
+            wind_sensitivity: 1.0,
+            output_map: OutputMap::Blend,
+            channel: None,
+        }
+    }
+}
+
+pub type SpeciesTable = FixedList<Species, MAX_SPECIES>;
+
+// Species picked by `r` in 0..1, by weight; `None` for an empty table
+// or when no weight is above zero
+pub fn pick(table: &[Species], r: f32) -> Option<usize> {
+    let total: f32 = table.iter().map(|s| s.weight.max(0.0)).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut target = r * total;
+    for (k, s) in table.iter().enumerate() {
+        let weight = s.weight.max(0.0);
+        if target < weight {
+            return Some(k);
+        }
+        target -= weight;
+    }
+    // Rounding left `r` close to 1 past the end
+    table.iter().rposition(|s| s.weight > 0.0)
+}
+
+// Fall speed and wind sensitivity multipliers of `species`, 1.0 without one
+pub fn scales(table: &[Species], species: Option<u8>) -> (f32, f32) {
+    match species.and_then(|k| table.get(k as usize)) {
+        Some(s) => (s.fall_speed, s.wind_sensitivity),
+        None => (1.0, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_pick() {
+        let table = [
+            Species { weight: 1.0, ..Species::default() },
+            Species { weight: 0.0, ..Species::default() },
+            Species { weight: 3.0, ..Species::default() },
+        ];
+        assert_eq!(pick(&table, 0.0), Some(0));
+        assert_eq!(pick(&table, 0.24), Some(0));
+        assert_eq!(pick(&table, 0.25), Some(2));
+        assert_eq!(pick(&table, 1.0), Some(2));
+        assert_eq!(pick(&[], 0.5), None);
+        assert_eq!(pick(&table[1..2], 0.5), None);
+
+        let heavy = Species { fall_speed: 0.5, wind_sensitivity: 0.2, ..Species::default() };
+        assert_eq!(scales(&[heavy], Some(0)), (0.5, 0.2));
+        assert_eq!(scales(&[heavy], None), (1.0, 1.0));
+    }
+}
@@ -0,0 +1,149 @@
+//! synth - Minimal PCM voice renderer for particle events
+//! Decaying sine voices rendered into caller buffers, no_std compatible
+
+use crate::{ParticleEvent, Settings};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VoiceConfig {
+    pub sample_rate: f32,
+    // Frequency of output 0, the u16 range spans `octaves` above it
+    pub freq_low: f32,
+    pub octaves: f32,
+    // Seconds for a voice to decay by 60 dB
+    pub decay: f32,
+    // Peak level of the largest particle
+    pub ground_gain: f32,
+    pub collision_gain: f32,
+    // Radius range mapped to amplitude
+    pub radius_min: f32,
+    pub radius_max: f32,
+}
+
+impl VoiceConfig {
+    pub fn from_settings(settings: &Settings, sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            freq_low: 110.0,
+            octaves: 4.0,
+            decay: 1.2,
+            ground_gain: 0.3,
+            collision_gain: 0.15,
+            radius_min: settings.particle_min_size,
+            radius_max: settings.particle_max_size,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct Voice {
+    phase: f32,
+    phase_inc: f32,
+    amp: f32,
+}
+
+// Polyphonic renderer, the quietest voice is stolen when all are busy
+pub struct Synth<const VOICES: usize> {
+    pub config: VoiceConfig,
+    voices: [Voice; VOICES],
+    // Per-sample amplitude multiplier derived from `decay`
+    decay_factor: f32,
+}
+
+impl<const VOICES: usize> Synth<VOICES> {
+    pub fn new(config: VoiceConfig) -> Self {
+        // -60 dB after `decay` seconds
+        let decay_factor = libm::powf(0.001, 1.0 / (config.decay * config.sample_rate).max(1.0));
+        Self {
+            config,
+            voices: [Voice::default(); VOICES],
+            decay_factor,
+        }
+    }
+
+    pub fn frequency_for(&self, output: u16) -> f32 {
+        self.config.freq_low * libm::exp2f(self.config.octaves * output as f32 / u16::MAX as f32)
+    }
+
+    pub fn amplitude_for(&self, radius: f32) -> f32 {
+        let span = self.config.radius_max - self.config.radius_min;
+        let t = if span > 0.0 { ((radius - self.config.radius_min) / span).clamp(0.0, 1.0) } else { 1.0 };
+        // Smallest particles stay audible
+        0.25 + 0.75 * t
+    }
+
+    pub fn trigger(&mut self, event: &ParticleEvent) {
+        let (output, radius, gain) = match *event {
+            ParticleEvent::GroundHit { output, radius, level, .. } => (output, radius, self.config.ground_gain * level),
+            ParticleEvent::ObstacleHit { output, radius, .. } | ParticleEvent::EdgeExit { output, radius, .. } => {
+                (output, radius, self.config.ground_gain)
+            }
+            ParticleEvent::Collision { output, radius, .. } | ParticleEvent::Merge { output, radius, .. } => {
+                (output, radius, self.config.collision_gain)
+            }
+        };
+        let freq = self.frequency_for(output);
+        let amp = self.amplitude_for(radius) * gain;
+        self.note_on(freq, amp);
+    }
+
+    pub fn note_on(&mut self, freq: f32, amp: f32) {
+        let mut slot = 0;
+        for (i, voice) in self.voices.iter().enumerate() {
+            if voice.amp < self.voices[slot].amp {
+                slot = i;
+            }
+        }
+        if let Some(voice) = self.voices.get_mut(slot) {
+            voice.phase = 0.0;
+            voice.phase_inc = freq / self.config.sample_rate;
+            voice.amp = amp;
+        }
+    }
+
+    // Overwrite `out` with the next mono samples
+    pub fn render(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            let mut mix = 0.0;
+            for voice in &mut self.voices {
+                if voice.amp < 1.0e-5 {
+                    continue;
+                }
+                mix += libm::sinf(voice.phase * 2.0 * core::f32::consts::PI) * voice.amp;
+                voice.phase += voice.phase_inc;
+                voice.phase -= libm::floorf(voice.phase);
+                voice.amp *= self.decay_factor;
+            }
+            *sample = mix;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Side;
+
+    #[test]
+    fn test_pitch_and_amplitude_mapping() {
+        let synth: Synth<4> = Synth::new(VoiceConfig::from_settings(&Settings::default(), 48_000.0));
+        assert_eq!(synth.frequency_for(0), 110.0);
+        assert!((synth.frequency_for(u16::MAX) - 1760.0).abs() < 0.01);
+        assert!(synth.amplitude_for(3.0) < synth.amplitude_for(10.0));
+    }
+
+    #[test]
+    fn test_event_renders_decaying_tone() {
+        let mut synth: Synth<4> = Synth::new(VoiceConfig::from_settings(&Settings::default(), 8_000.0));
+        let mut buf = [0.0f32; 800];
+        synth.render(&mut buf);
+        assert!(buf.iter().all(|&s| s == 0.0));
+
+        synth.trigger(&ParticleEvent::GroundHit { index: 0, output: 20_000, radius: 10.0, time: 0.0, bounce: 0, level: 1.0, speed: 8.0, zone: None, floor: Side::Bottom, species: None });
+        synth.render(&mut buf);
+        let peak = |b: &[f32]| b.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        let early = peak(&buf[..100]);
+        let late = peak(&buf[700..]);
+        assert!(early > 0.1 && early <= 0.3);
+        assert!(late < early);
+    }
+}
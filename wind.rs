@@ -0,0 +1,90 @@
+//! wind - Position dependent wind field
+//! Drifting value noise with gusts, sampled per particle, no_std compatible
+
+use crate::Settings;
+
+// Lattice hash mapped to -1.0..=1.0
+fn lattice(x: i32, y: i32, seed: u32) -> f32 {
+    let mut h = seed ^ (x as u32).wrapping_mul(0x27D4_EB2D) ^ (y as u32).wrapping_mul(0x1656_67B1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xC2B2_AE35);
+    h ^= h >> 16;
+    h as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+// CHANGE: Smooth 2D value noise in -1.0..=1.0
+// REASON: Cheaper than Perlin on small MCUs and smooth enough for drift
+pub fn value_noise(x: f32, y: f32, seed: u32) -> f32 {
+    let (fx, fy) = (libm::floorf(x), libm::floorf(y));
+    let (ix, iy) = (fx as i32, fy as i32);
+    let (tx, ty) = (x - fx, y - fy);
+    // Smoothstep fade, no kinks at the lattice lines
+    let (sx, sy) = (tx * tx * (3.0 - 2.0 * tx), ty * ty * (3.0 - 2.0 * ty));
+
+    let top = lattice(ix, iy, seed) + (lattice(ix + 1, iy, seed) - lattice(ix, iy, seed)) * sx;
+    let bottom = lattice(ix, iy + 1, seed) + (lattice(ix + 1, iy + 1, seed) - lattice(ix, iy + 1, seed)) * sx;
+    top + (bottom - top) * sy
+}
+
+// Wind velocity at (x, y) in px/s, before the particle's `wind_sensitivity`
+pub fn wind_at(settings: &Settings, time: f32, x: f32, y: f32) -> (f32, f32) {
+    let s = settings;
+    if s.wind_field_strength == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (dir_y, dir_x) = (libm::sinf(s.wind_direction), libm::cosf(s.wind_direction));
+
+    // The turbulence pattern travels downwind
+    let scale = s.wind_turbulence_scale.max(1.0);
+    let u = (x - dir_x * s.wind_drift * time) / scale;
+    let v = (y - dir_y * s.wind_drift * time) / scale;
+    let turb_x = value_noise(u, v, s.rng_seed);
+    let turb_y = value_noise(u + 31.7, v + 17.3, s.rng_seed);
+
+    // Gusts swell and fade everywhere at once
+    let gust = 1.0 + s.wind_gust * value_noise(time * 0.5, 0.5, s.rng_seed ^ 0x9E37_79B9);
+
+    let strength = s.wind_field_strength * gust.max(0.0);
+    (strength * (dir_x + turb_x), strength * (dir_y + turb_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_and_field() {
+        // Smooth, bounded and repeatable
+        let mut prev = value_noise(0.0, 0.3, 7);
+        for i in 1..400 {
+            let n = value_noise(i as f32 * 0.01, 0.3, 7);
+            assert!((-1.0..=1.0).contains(&n));
+            assert!((n - prev).abs() < 0.05);
+            prev = n;
+        }
+        assert_eq!(value_noise(2.5, 1.5, 7), value_noise(2.5, 1.5, 7));
+        assert_ne!(value_noise(2.5, 1.5, 7), value_noise(2.5, 1.5, 8));
+
+        let off = Settings::default();
+        assert_eq!(wind_at(&off, 3.0, 100.0, 50.0), (0.0, 0.0));
+
+        // Turbulence averages out, leaving a push along the direction
+        let field = Settings {
+            wind_field_strength: 10.0,
+            wind_direction: core::f32::consts::FRAC_PI_2,
+            wind_gust: 0.0,
+            wind_turbulence_scale: 10.0,
+            ..Settings::default()
+        };
+        let (mut sum_x, mut sum_y) = (0.0, 0.0);
+        for i in 0..400 {
+            let (wx, wy) = wind_at(&field, 3.0, (i % 20) as f32 * 37.0, (i / 20) as f32 * 23.0);
+            sum_x += wx / 400.0;
+            sum_y += wy / 400.0;
+        }
+        assert!(sum_y > 8.0 && sum_y < 12.0);
+        assert!(sum_x.abs() < 2.0);
+    }
+}
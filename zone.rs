@@ -0,0 +1,56 @@
+//! zone - Ground zones with their own outputs and triggers
+//! Spans of the ground line and the lookup of the span a hit lands in
+
+use crate::list::FixedList;
+
+// Upper bound on zones, one per channel of a 4-channel DAC
+pub const MAX_GROUND_ZONES: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GroundZone {
+    // Span of the ground line, from x0 up to but not including x1
+    pub x0: f32,
+    pub x1: f32,
+    // MIDI note for every hit, `None` derives it from the output like other ground hits
+    pub note: Option<u8>,
+}
+
+impl GroundZone {
+    pub fn contains(&self, x: f32) -> bool {
+        x >= self.x0.min(self.x1) && x < self.x0.max(self.x1)
+    }
+}
+
+// Unused slots of the list
+impl Default for GroundZone {
+    fn default() -> Self {
+        Self { x0: 0.0, x1: 0.0, note: None }
+    }
+}
+
+pub type GroundZones = FixedList<GroundZone, MAX_GROUND_ZONES>;
+
+// First zone containing `x`, overlapping zones go to the earlier one
+pub fn zone_at(zones: &[GroundZone], x: f32) -> Option<usize> {
+    zones.iter().position(|zone| zone.contains(x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_lookup() {
+        let zones = [
+            GroundZone { x0: 0.0, x1: 100.0, note: Some(36) },
+            GroundZone { x0: 200.0, x1: 100.0, note: Some(38) },
+            GroundZone { x0: 150.0, x1: 320.0, note: None },
+        ];
+        assert_eq!(zone_at(&zones, 0.0), Some(0));
+        assert_eq!(zone_at(&zones, 100.0), Some(1));
+        assert_eq!(zone_at(&zones, 180.0), Some(1));
+        assert_eq!(zone_at(&zones, 200.0), Some(2));
+        assert_eq!(zone_at(&zones, 320.0), None);
+        assert_eq!(zone_at(&zones, -1.0), None);
+    }
+}